@@ -0,0 +1,212 @@
+//! Pinning and priority of worker threads.
+
+use crate::ThreadPriority;
+
+//-----------------------------------------------------------------------------
+#[cfg(target_os = "linux")]
+mod sys {
+    // `cpu_set_t` of glibc and musl, 1024 bits
+    #[repr(C)]
+    pub(super) struct CpuSet(pub(super) [u64; 16]);
+
+    pub(super) const PRIO_PROCESS: i32 = 0;
+
+    extern "C" {
+        pub(super) fn sched_setaffinity(pid: i32, size: usize, mask: *const CpuSet) -> i32;
+        pub(super) fn setpriority(which: i32, who: u32, prio: i32) -> i32;
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+    pub(super) const QOS_CLASS_UTILITY: u32 = 0x11;
+    pub(super) const QOS_CLASS_BACKGROUND: u32 = 0x09;
+
+    extern "C" {
+        pub(super) fn pthread_set_qos_class_self_np(qos: u32, relative_priority: i32) -> i32;
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use core::ffi::c_void;
+
+    pub(super) const THREAD_PRIORITY_BELOW_NORMAL: i32 = -1;
+    pub(super) const THREAD_PRIORITY_LOWEST: i32 = -2;
+
+    extern "system" {
+        pub(super) fn GetCurrentThread() -> *mut c_void;
+        pub(super) fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+    }
+}
+
+// Restrict the current thread to `cores`; return `false` if not supported on
+// this platform or if the request was rejected, e.g. for an offline core.
+#[cfg(target_os = "linux")]
+pub(crate) fn pin_current_thread(cores: &[usize]) -> bool {
+    let mut set = sys::CpuSet([0; 16]);
+    for &c in cores.iter().filter(|c| **c < 1024) {
+        set.0[c / 64] |= 1 << (c % 64);
+    }
+    // SAFETY: `set` is a valid `cpu_set_t` for the duration of the call, pid
+    // `0` selects the calling thread.
+    unsafe { sys::sched_setaffinity(0, std::mem::size_of::<sys::CpuSet>(), &set) == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pin_current_thread(_cores: &[usize]) -> bool {
+    false
+}
+
+// Lower the priority of the current thread; return `false` if not supported
+// on this platform or if the request was rejected.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_current_thread_priority(priority: ThreadPriority) -> bool {
+    let nice = match priority {
+        ThreadPriority::Normal => return true,
+        ThreadPriority::Low => 10,
+        ThreadPriority::Background => 19,
+    };
+    // SAFETY: no pointers are passed; with NPTL the nice value is per thread
+    // and who `0` selects the calling thread.
+    unsafe { sys::setpriority(sys::PRIO_PROCESS, 0, nice) == 0 }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn set_current_thread_priority(priority: ThreadPriority) -> bool {
+    let qos = match priority {
+        ThreadPriority::Normal => return true,
+        ThreadPriority::Low => sys::QOS_CLASS_UTILITY,
+        ThreadPriority::Background => sys::QOS_CLASS_BACKGROUND,
+    };
+    // SAFETY: no pointers are passed, applies to the calling thread.
+    unsafe { sys::pthread_set_qos_class_self_np(qos, 0) == 0 }
+}
+
+#[cfg(windows)]
+pub(crate) fn set_current_thread_priority(priority: ThreadPriority) -> bool {
+    let level = match priority {
+        ThreadPriority::Normal => return true,
+        ThreadPriority::Low => sys::THREAD_PRIORITY_BELOW_NORMAL,
+        ThreadPriority::Background => sys::THREAD_PRIORITY_LOWEST,
+    };
+    // SAFETY: the pseudo handle of the current thread needs no closing.
+    unsafe { sys::SetThreadPriority(sys::GetCurrentThread(), level) != 0 }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub(crate) fn set_current_thread_priority(_priority: ThreadPriority) -> bool {
+    false
+}
+
+//-----------------------------------------------------------------------------
+/// Cores of each NUMA node, indexed by node, as reported by
+/// `/sys/devices/system/node`; empty if the topology is not available.
+pub fn numa_nodes() -> Vec<Vec<usize>> {
+    let Ok(dir) = std::fs::read_dir("/sys/devices/system/node") else {
+        return Vec::new();
+    };
+    let mut nodes: Vec<(usize, Vec<usize>)> = dir
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            let id = name.strip_prefix("node")?.parse().ok()?;
+            let list = std::fs::read_to_string(e.path().join("cpulist")).ok()?;
+            Some((id, parse_cpu_list(&list)))
+        })
+        .collect();
+    nodes.sort();
+    nodes.into_iter().map(|(_, cores)| cores).collect()
+}
+
+// Parse a kernel cpu list such as `0-3,8,10-11`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|r| match r.split_once('-') {
+            Some((a, b)) => Some(a.parse().ok()?..=b.parse().ok()?),
+            None => r.parse().ok().map(|c| c..=c),
+        })
+        .flatten()
+        .collect()
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn parse_cpu_list_test() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert!(parse_cpu_list("").is_empty());
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests {
+    use crate::{kernel, par_in_place_map_with, ParConfig, ParError, Scheduling, ThreadPriority};
+    extern "C" {
+        fn sched_getcpu() -> i32;
+    }
+    #[test]
+    fn pin_threads_test() -> Result<(), ParError> {
+        let cfg = ParConfig::builder().threads(3).cores(vec![0]).build();
+        let kernel_fun = |d: &mut [i32]| {
+            // SAFETY: no preconditions.
+            d.fill(unsafe { sched_getcpu() });
+        };
+        let mut dest = vec![-1; 30];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|c| *c == 0));
+        let cfg = ParConfig::builder().threads(2).pin_threads(true).build();
+        par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|c| *c >= 0));
+        // pinned under any scheduling, set before or after the nodes
+        let nodes = || vec![vec![0], vec![0]];
+        for cfg in [
+            ParConfig::builder().threads(4).numa_nodes(nodes()),
+            ParConfig::builder()
+                .threads(4)
+                .scheduling(Scheduling::Dynamic)
+                .numa_nodes(nodes()),
+            ParConfig::builder()
+                .threads(4)
+                .numa_nodes(nodes())
+                .scheduling(Scheduling::Cyclic),
+        ] {
+            let cfg = cfg.chunk_size(3).build();
+            dest.fill(-1);
+            par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+            assert!(dest.iter().all(|c| *c == 0));
+        }
+        let cfg = ParConfig::builder()
+            .scheduling(Scheduling::Dynamic)
+            .numa_nodes(nodes())
+            .build();
+        assert_eq!(cfg.scheduling(), Scheduling::Dynamic);
+        Ok(())
+    }
+    #[test]
+    fn priority_test() -> Result<(), ParError> {
+        extern "C" {
+            fn getpriority(which: i32, who: u32) -> i32;
+        }
+        let cfg = ParConfig::builder()
+            .threads(2)
+            .priority(ThreadPriority::Background)
+            .build();
+        // SAFETY: no preconditions.
+        let nice = || unsafe { getpriority(0, 0) };
+        let caller = nice();
+        let mut dest = vec![0; 20];
+        par_in_place_map_with(
+            &cfg,
+            &mut dest,
+            kernel!(move |d: &mut [i32]| d.fill(nice())),
+        )?;
+        assert!(dest.iter().all(|n| *n == 19));
+        assert_eq!(nice(), caller);
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Search of byte patterns and splitting on delimiters.
+
+use crate::{exec, split, ParError};
+use std::ops::Range;
+
+// Split the start positions of the matches of a needle of length `m` and
+// return each range with the part of `haystack` it needs: the sub-ranges
+// overlap by `m - 1` bytes so that matches straddling a boundary are found.
+fn windows(haystack: &[u8], m: usize, num_threads: usize) -> Vec<(Range<usize>, &[u8])> {
+    let starts = (haystack.len() + 1).saturating_sub(m);
+    split::ranges(starts, num_threads)
+        .into_iter()
+        .map(|r| {
+            let end = r.end + m - 1;
+            (r.clone(), &haystack[r.start..end])
+        })
+        .collect()
+}
+
+// Positions of the matches of `needle` in `s`.
+fn matches<'a>(s: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    s.windows(needle.len())
+        .enumerate()
+        .filter(move |(_, w)| *w == needle)
+        .map(|(i, _)| i)
+}
+
+//-----------------------------------------------------------------------------
+/// Position of the first occurrence of `needle` in `haystack`.
+///
+/// The haystack is split into sub-ranges overlapping by `needle.len() - 1`
+/// bytes, so that occurrences straddling a boundary are found; an empty
+/// needle matches at position `0`.
+pub fn par_find_pattern(
+    haystack: &[u8],
+    needle: &[u8],
+    num_threads: usize,
+) -> Result<Option<usize>, ParError> {
+    if needle.is_empty() {
+        return Ok(Some(0));
+    }
+    let found = exec::run(
+        num_threads,
+        windows(haystack, needle.len(), num_threads),
+        |(r, s)| matches(s, needle).next().map(|i| r.start + i),
+    )?;
+    Ok(found.into_iter().flatten().next())
+}
+
+//-----------------------------------------------------------------------------
+/// Number of occurrences of `needle` in `haystack`, including overlapping
+/// ones, see [`par_find_pattern`]; an empty needle matches at every position
+/// including the end, `haystack.len() + 1` times.
+pub fn par_count_pattern(
+    haystack: &[u8],
+    needle: &[u8],
+    num_threads: usize,
+) -> Result<usize, ParError> {
+    if needle.is_empty() {
+        return Ok(haystack.len() + 1);
+    }
+    let counts = exec::run(
+        num_threads,
+        windows(haystack, needle.len(), num_threads),
+        |(_, s)| matches(s, needle).count(),
+    )?;
+    Ok(counts.into_iter().sum())
+}
+
+//-----------------------------------------------------------------------------
+/// Ranges of the pieces of `haystack` separated by `delimiter`, like
+/// [`slice::split`]: `k` delimiters give `k + 1` possibly empty pieces.
+///
+/// The occurrences are found in parallel as in [`par_find_pattern`], then
+/// the boundaries are fixed up in order: an occurrence overlapping the
+/// previous delimiter, such as the second one of `"aa"` in `"aaa"`, is not a
+/// delimiter.
+///
+/// ```rust,ignore
+/// for line in par_split(&text, b"\n", 8)? {
+///     process(&text[line]);
+/// }
+/// ```
+///
+/// Panics if `delimiter` is empty.
+pub fn par_split(
+    haystack: &[u8],
+    delimiter: &[u8],
+    num_threads: usize,
+) -> Result<Vec<Range<usize>>, ParError> {
+    assert!(!delimiter.is_empty(), "empty delimiter");
+    let m = delimiter.len();
+    let found = exec::run(num_threads, windows(haystack, m, num_threads), |(r, s)| {
+        matches(s, delimiter)
+            .map(|i| r.start + i)
+            .collect::<Vec<_>>()
+    })?;
+    let mut pieces = Vec::with_capacity(found.iter().map(Vec::len).sum::<usize>() + 1);
+    let mut start = 0;
+    for i in found.into_iter().flatten() {
+        if i >= start {
+            pieces.push(start..i);
+            start = i + m;
+        }
+    }
+    pieces.push(start..haystack.len());
+    Ok(pieces)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn par_find_pattern_test() -> Result<(), ParError> {
+        let mut haystack = vec![b'.'; 1000];
+        // straddles the boundary between the first two of 4 sub-ranges
+        haystack[248..253].copy_from_slice(b"match");
+        haystack[700..705].copy_from_slice(b"match");
+        assert_eq!(par_find_pattern(&haystack, b"match", 4)?, Some(248));
+        assert_eq!(par_count_pattern(&haystack, b"match", 4)?, 2);
+        assert_eq!(par_find_pattern(&haystack, b"other", 4)?, None);
+        assert_eq!(par_count_pattern(b"aaaa", b"aa", 3)?, 3);
+        assert_eq!(par_find_pattern(b"ab", b"abc", 3)?, None);
+        assert_eq!(par_find_pattern(b"ab", b"", 3)?, Some(0));
+        assert_eq!(par_count_pattern(b"ab", b"", 3)?, 3);
+        Ok(())
+    }
+    #[test]
+    fn par_split_test() -> Result<(), ParError> {
+        let text: Vec<u8> = (0..5000)
+            .flat_map(|i| format!("{i}\r\n").into_bytes())
+            .collect();
+        let expected: Vec<&[u8]> = text.split(|b| *b == b'\n').collect();
+        for n in [1, 4, 7] {
+            let lines = par_split(&text, b"\n", n)?;
+            assert!(lines
+                .into_iter()
+                .map(|r| &text[r])
+                .eq(expected.iter().copied()));
+            let lines = par_split(&text, b"\r\n", n)?;
+            assert_eq!(lines.len(), 5001);
+            assert_eq!(&text[lines[4999].clone()], b"4999");
+        }
+        let split = |h: &[u8], d: &[u8]| par_split(h, d, 3);
+        assert_eq!(split(b"aaaaa", b"aa")?, [0..0, 2..2, 4..5]);
+        assert_eq!(split(b"", b",")?, vec![0..0]);
+        assert_eq!(split(b",x,", b",")?, [0..0, 1..2, 3..3]);
+        assert_eq!(split(b"ab", b"abc")?, vec![0..2]);
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+//! Cooperative cancellation.
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+//-----------------------------------------------------------------------------
+/// Shared flag used to cancel a parallel call.
+///
+/// Clones refer to the same flag: pass one to a `*_cancellable` function and
+/// call [`CancelToken::cancel`] from any thread to skip the sub-ranges not yet
+/// started. Long-running kernels can capture a clone and poll
+/// [`CancelToken::is_cancelled`] to bail out early.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Request cancellation.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+    /// Return `true` if cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
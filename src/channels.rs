@@ -0,0 +1,128 @@
+//! Conversion between interleaved and planar multi-channel data.
+
+use crate::{exec, split, ParError};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+// Frame ranges, one per thread, and the same ranges in elements of the
+// interleaved sequence.
+fn frame_ranges(
+    frames: usize,
+    channels: usize,
+    num_threads: usize,
+) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let ranges = split::ranges(frames, num_threads);
+    let scaled = ranges
+        .iter()
+        .map(|r| r.start * channels..r.end * channels)
+        .collect();
+    (ranges, scaled)
+}
+
+//-----------------------------------------------------------------------------
+/// Split the interleaved frames of `src`, one element per channel, into one
+/// sequence per channel: `dests[c][i] = src[i * dests.len() + c]`.
+///
+/// Every destination must hold `src.len() / dests.len()` elements, otherwise
+/// [`ParError::LengthMismatch`] is returned. Each thread converts a range of
+/// frames.
+///
+/// ```rust,ignore
+/// let (mut left, mut right) = (vec![0.0; n], vec![0.0; n]);
+/// par_deinterleave(&stereo, &mut [&mut left, &mut right], 4)?;
+/// ```
+pub fn par_deinterleave<T: Clone + Send + Sync>(
+    src: &[T],
+    dests: &mut [&mut [T]],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    let channels = dests.len();
+    let frames = dests.first().map_or(0, |d| d.len());
+    for d in dests.iter() {
+        crate::check_len(frames, d.len())?;
+    }
+    crate::check_len(src.len(), frames * channels)?;
+    let (ranges, scaled) = frame_ranges(frames, channels, num_threads);
+    // per range, the matching part of every channel
+    let mut parts: Vec<Vec<&mut [T]>> = ranges.iter().map(|_| Vec::new()).collect();
+    for d in dests.iter_mut() {
+        for (p, c) in parts.iter_mut().zip(split::split_ranges(&mut **d, &ranges)) {
+            p.push(c);
+        }
+    }
+    let jobs: Vec<_> = parts.into_iter().zip(scaled).collect();
+    exec::run(num_threads, jobs, |(mut planes, r)| {
+        let frames = &src[r];
+        for (c, plane) in planes.iter_mut().enumerate() {
+            let samples = frames.iter().skip(c).step_by(channels);
+            for (d, s) in plane.iter_mut().zip(samples) {
+                *d = s.clone();
+            }
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Interleave one sequence per channel into frames of one element per
+/// channel: `dest[i * srcs.len() + c] = srcs[c][i]`, see
+/// [`par_deinterleave`].
+pub fn par_interleave<T: Clone + Send + Sync>(
+    srcs: &[&[T]],
+    dest: &mut [T],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    let channels = srcs.len();
+    let frames = srcs.first().map_or(0, |s| s.len());
+    for s in srcs {
+        crate::check_len(frames, s.len())?;
+    }
+    crate::check_len(frames * channels, dest.len())?;
+    let (ranges, scaled) = frame_ranges(frames, channels, num_threads);
+    let jobs: Vec<_> = split::split_ranges(dest, &scaled)
+        .into_iter()
+        .zip(ranges)
+        .collect();
+    exec::run(num_threads, jobs, |(d, r)| {
+        for (c, plane) in srcs.iter().enumerate() {
+            let samples = d.iter_mut().skip(c).step_by(channels);
+            for (d, s) in samples.zip(&plane[r.clone()]) {
+                *d = s.clone();
+            }
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    #[test]
+    fn interleave_test() -> Result<(), ParError> {
+        // 5.1 frames
+        let src: Vec<u32> = (0..6 * 1000).collect();
+        let mut planes = vec![vec![0; 1000]; 6];
+        let mut dests: Vec<&mut [u32]> = planes.iter_mut().map(|p| &mut p[..]).collect();
+        par_deinterleave(&src, &mut dests, 4)?;
+        for (c, p) in planes.iter().enumerate() {
+            assert!(p.iter().enumerate().all(|(i, e)| *e as usize == i * 6 + c));
+        }
+        let srcs: Vec<&[u32]> = planes.iter().map(|p| &p[..]).collect();
+        let mut dest = vec![0; src.len()];
+        par_interleave(&srcs, &mut dest, 3)?;
+        assert_eq!(dest, src);
+        let r = par_interleave(&srcs[..2], &mut dest, 3);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: 2000,
+                dest: 6000
+            })
+        ));
+        par_interleave::<u32>(&[], &mut [], 3)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+//! Kernels receiving the sub-range together with its metadata.
+
+use crate::{exec, split, ParError};
+use std::ops::Range;
+use std::sync::Arc;
+
+type ChunkKernelFun<'a, T> = dyn Fn(Chunk<'_, T>) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Sub-range of a sequence passed to the kernel of [`par_chunks_for_each`].
+#[derive(Debug)]
+pub struct Chunk<'a, T> {
+    /// Index of the sub-range, from `0` to the number of sub-ranges.
+    pub index: usize,
+    /// Element range of the sub-range in the sequence.
+    pub range: Range<usize>,
+    /// Elements of the sub-range.
+    pub data: &'a mut [T],
+}
+
+//-----------------------------------------------------------------------------
+/// Modify sequence in-place, the kernel receives each sub-range as a
+/// [`Chunk`] holding its index and element range.
+pub fn par_chunks_for_each<T: Send>(
+    dest: &mut [T],
+    num_threads: usize,
+    fr: Arc<ChunkKernelFun<T>>,
+) -> Result<(), ParError> {
+    let chunks = split::chunks(dest, num_threads);
+    exec::run(
+        num_threads,
+        chunks.into_iter().enumerate().collect(),
+        |(index, (range, data))| fr(Chunk { index, range, data }),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_chunks_for_each_test() -> Result<(), ParError> {
+        let mut dest = vec![0_usize; 10];
+        let kernel_fun = |c: Chunk<'_, usize>| {
+            assert_eq!(c.range.len(), c.data.len());
+            for (d, i) in c.data.iter_mut().zip(c.range) {
+                *d = c.index * 100 + i;
+            }
+        };
+        par_chunks_for_each(&mut dest, 3, kernel!(kernel_fun))?;
+        assert_eq!(dest, [0, 1, 2, 3, 104, 105, 106, 207, 208, 209]);
+        Ok(())
+    }
+}
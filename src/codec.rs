@@ -0,0 +1,208 @@
+//! Parallel encoding of independent frames, e.g. for compression.
+
+use crate::{exec, split, ChunkError, CodecError, ParError};
+use std::ops::Range;
+
+// Container layout, integers in little endian:
+// - magic
+// - number of frames `n`: u64
+// - `n` times: encoded length, decoded length: u64
+// - the encoded frames
+const MAGIC: &[u8; 4] = b"PSQ1";
+const HEADER_LEN: usize = MAGIC.len() + 8;
+const ENTRY_LEN: usize = 16;
+
+//-----------------------------------------------------------------------------
+/// Encoding applied to each frame independently, such as a compression
+/// algorithm.
+pub trait ChunkCodec: Sync {
+    /// Error returned when a frame cannot be decoded.
+    type Error: Send;
+    /// Encode a frame.
+    fn encode(&self, src: &[u8]) -> Vec<u8>;
+    /// Decode a frame produced by [`ChunkCodec::encode`].
+    fn decode(&self, src: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+//-----------------------------------------------------------------------------
+/// Split `src` into frames of `frame_size` bytes, encode them in parallel
+/// with `codec` and return a container holding the frames preceded by a
+/// table of their encoded and decoded lengths, see [`par_decode_chunks`].
+///
+/// ```rust,ignore
+/// let packed = par_encode_chunks(&data, 1 << 20, 8, &Lz4Codec)?;
+/// let unpacked = par_decode_chunks(&packed, 8, &Lz4Codec)?;
+/// ```
+pub fn par_encode_chunks<C: ChunkCodec>(
+    src: &[u8],
+    frame_size: usize,
+    num_threads: usize,
+    codec: &C,
+) -> Result<Vec<u8>, ParError> {
+    let ranges = split::fixed_ranges(src.len(), frame_size);
+    let frames = exec::run_chunks(num_threads, split::zip_ranges(src, ranges), |(r, s)| {
+        (r.len(), codec.encode(s))
+    })?;
+    let data_len: usize = frames.iter().map(|(_, f)| f.len()).sum();
+    let mut dest = Vec::with_capacity(HEADER_LEN + frames.len() * ENTRY_LEN + data_len);
+    dest.extend_from_slice(MAGIC);
+    dest.extend_from_slice(&(frames.len() as u64).to_le_bytes());
+    for (len, f) in &frames {
+        dest.extend_from_slice(&(f.len() as u64).to_le_bytes());
+        dest.extend_from_slice(&(*len as u64).to_le_bytes());
+    }
+    for (_, f) in frames {
+        dest.extend_from_slice(&f);
+    }
+    Ok(dest)
+}
+
+//-----------------------------------------------------------------------------
+/// Decode in parallel the frames of a container returned by
+/// [`par_encode_chunks`], then concatenate them.
+///
+/// A container which cannot be parsed, or declares more than `isize::MAX`
+/// decoded bytes, is reported as a single [`CodecError::Malformed`];
+/// otherwise every frame failing to decode, or decoding to a different length
+/// than declared, is reported with its byte range in the container. The
+/// output is only allocated once every frame is decoded, so a crafted table
+/// cannot make it larger than what the codec actually produced.
+pub fn par_decode_chunks<C: ChunkCodec>(
+    src: &[u8],
+    num_threads: usize,
+    codec: &C,
+) -> Result<Vec<u8>, ParError<CodecError<C::Error>>> {
+    let Some(frames) = frame_table(src) else {
+        return Err(ParError::Kernel(vec![ChunkError {
+            index: 0,
+            range: 0..src.len(),
+            error: CodecError::Malformed,
+        }]));
+    };
+    let results = exec::run(num_threads, frames, |(r, expected)| {
+        let f = match codec.decode(&src[r.clone()]) {
+            Ok(f) if f.len() == expected => Ok(f),
+            Ok(f) => Err(CodecError::Length {
+                expected,
+                actual: f.len(),
+            }),
+            Err(e) => Err(CodecError::Codec(e)),
+        };
+        (r, f)
+    })?;
+    let mut parts = Vec::with_capacity(results.len());
+    let status = results
+        .into_iter()
+        .map(|(r, f)| (r, f.map(|f| parts.push(f))))
+        .collect();
+    crate::chunk_errors(status)?;
+    Ok(parts.concat())
+}
+
+// Byte range of each encoded frame in the container, with its declared
+// decoded length.
+type FrameTable = Vec<(Range<usize>, usize)>;
+
+// Parse the frame table, `None` if the container is malformed or its decoded
+// frames could not fit in a single allocation.
+fn frame_table(src: &[u8]) -> Option<FrameTable> {
+    let read = |at: usize| -> Option<usize> {
+        let b = src.get(at..at + 8)?;
+        usize::try_from(u64::from_le_bytes(b.try_into().ok()?)).ok()
+    };
+    if src.get(..MAGIC.len())? != MAGIC {
+        return None;
+    }
+    let n = read(MAGIC.len())?;
+    let table_end = n.checked_mul(ENTRY_LEN)?.checked_add(HEADER_LEN)?;
+    let (mut enc, mut dec) = (table_end, 0_usize);
+    let mut frames = Vec::with_capacity(n.min(src.len() / ENTRY_LEN));
+    for i in 0..n {
+        let at = HEADER_LEN + i * ENTRY_LEN;
+        let (e, d) = (read(at)?, read(at + 8)?);
+        frames.push((enc..enc.checked_add(e)?, d));
+        enc += e;
+        dec = dec.checked_add(d).filter(|&t| t <= isize::MAX as usize)?;
+    }
+    (enc == src.len()).then_some(frames)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // byte-level run-length encoding as (count, byte) pairs
+    struct Rle;
+    impl ChunkCodec for Rle {
+        type Error = &'static str;
+        fn encode(&self, src: &[u8]) -> Vec<u8> {
+            src.chunk_by(|a, b| a == b)
+                .flat_map(|g| g.chunks(255))
+                .flat_map(|g| [g.len() as u8, g[0]])
+                .collect()
+        }
+        fn decode(&self, src: &[u8]) -> Result<Vec<u8>, &'static str> {
+            if !src.len().is_multiple_of(2) {
+                return Err("odd length");
+            }
+            Ok(src
+                .chunks(2)
+                .flat_map(|p| std::iter::repeat_n(p[1], p[0].into()))
+                .collect())
+        }
+    }
+    #[test]
+    fn codec_test() -> Result<(), Box<dyn std::error::Error>> {
+        let src: Vec<u8> = (0..100_000_u32).map(|i| (i / 1000) as u8).collect();
+        let packed = par_encode_chunks(&src, 4096, 4, &Rle)?;
+        assert!(packed.len() < src.len() / 10);
+        assert_eq!(par_decode_chunks(&packed, 4, &Rle)?, src);
+        let empty = par_encode_chunks(&[], 4096, 4, &Rle)?;
+        assert!(par_decode_chunks(&empty, 4, &Rle)?.is_empty());
+        // truncated container
+        let Err(ParError::Kernel(e)) = par_decode_chunks(&packed[..100], 4, &Rle) else {
+            panic!("malformed container decoded");
+        };
+        assert_eq!(e[0].error, CodecError::Malformed);
+        // crafted tables with one empty frame: declared to decode to more
+        // than isize::MAX bytes, then to a length which must not be allocated
+        // before decoding
+        let crafted = |declared: u64| {
+            let mut c = MAGIC.to_vec();
+            c.extend_from_slice(&1_u64.to_le_bytes());
+            c.extend_from_slice(&0_u64.to_le_bytes());
+            c.extend_from_slice(&declared.to_le_bytes());
+            c
+        };
+        let Err(ParError::Kernel(e)) = par_decode_chunks(&crafted(u64::MAX), 4, &Rle) else {
+            panic!("oversized container decoded");
+        };
+        assert_eq!(e[0].error, CodecError::Malformed);
+        let Err(ParError::Kernel(e)) = par_decode_chunks(&crafted(1 << 40), 4, &Rle) else {
+            panic!("crafted frame decoded");
+        };
+        assert_eq!(e.len(), 1);
+        assert!(matches!(
+            e[0].error,
+            CodecError::Length {
+                expected: 0x100_0000_0000,
+                actual: 0
+            }
+        ));
+        // corrupted frame: the first count of the second frame
+        let mut bad = packed.clone();
+        let second = HEADER_LEN + 25 * ENTRY_LEN + packed[HEADER_LEN] as usize;
+        bad[second] = 1;
+        let Err(ParError::Kernel(e)) = par_decode_chunks(&bad, 4, &Rle) else {
+            panic!("corrupted frame decoded");
+        };
+        assert_eq!(e.len(), 1);
+        assert_eq!(e[0].index, 1);
+        assert!(matches!(
+            e[0].error,
+            CodecError::Length { expected: 4096, .. }
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+//! Execution parameters.
+
+#[cfg(feature = "std")]
+use crate::StatsCollector;
+use crate::{
+    split, CancelToken, Chunking, PanicPolicy, ParError, Scheduling, Spawner, ThreadPriority,
+};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+// Without a clock no deadline is ever set.
+#[cfg(not(feature = "std"))]
+#[derive(Clone, Copy)]
+pub(crate) enum Instant {}
+
+// Default cache line size in bytes.
+const CACHE_LINE_SIZE: usize = 64;
+
+type HookFun = dyn Fn(usize) + Send + Sync;
+type ProgressFun = dyn Fn(usize, usize) + Send + Sync;
+type WeightFun = dyn Fn(usize) -> u64 + Send + Sync;
+#[cfg(feature = "std")]
+type ChunkHookFun = dyn Fn(usize, usize, Range<usize>, Duration) + Send + Sync;
+
+// Callback stored in the configuration.
+struct Hook<F: ?Sized>(Arc<F>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Hook(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Execution parameters shared by the `*_with` functions.
+///
+/// ```rust,ignore
+/// let cfg = ParConfig::builder().threads(8).build();
+/// par_map_with(&cfg, &src, &mut dest, kernel!(kernel_fun))?;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParConfig {
+    num_threads: usize,
+    chunk_size: Option<usize>,
+    chunking: Chunking,
+    weight: Option<Hook<WeightFun>>,
+    scheduling: Scheduling,
+    panic_policy: PanicPolicy,
+    deterministic: bool,
+    unordered: bool,
+    cancel_token: Option<CancelToken>,
+    on_thread_start: Option<Hook<HookFun>>,
+    on_thread_end: Option<Hook<HookFun>>,
+    on_progress: Option<Hook<ProgressFun>>,
+    #[cfg(feature = "std")]
+    on_chunk: Option<Hook<ChunkHookFun>>,
+    timeout: Option<Duration>,
+    pin_threads: bool,
+    priority: ThreadPriority,
+    cores: Option<Vec<usize>>,
+    numa_nodes: Option<Vec<Vec<usize>>>,
+    thread_name_prefix: Option<String>,
+    stack_size: Option<usize>,
+    cache_line: Option<usize>,
+    simd_width: Option<usize>,
+    spawner: Option<Hook<dyn Spawner>>,
+    #[cfg(feature = "std")]
+    stats: Option<StatsCollector>,
+}
+
+impl ParConfig {
+    /// Create a builder initialised with the default parameters.
+    pub fn builder() -> ParConfigBuilder {
+        ParConfigBuilder::default()
+    }
+    /// Number of threads to spawn.
+    pub fn num_threads(&self) -> usize {
+        crate::resolve_threads(self.num_threads)
+    }
+    /// Number of elements per sub-range, if set.
+    pub fn chunk_size(&self) -> Option<usize> {
+        self.chunk_size
+    }
+    /// Sizing of the sub-ranges.
+    pub fn chunking(&self) -> Chunking {
+        self.chunking
+    }
+    /// Assignment of the sub-ranges to the threads.
+    pub fn scheduling(&self) -> Scheduling {
+        self.scheduling
+    }
+    /// Handling of kernel panics.
+    pub fn panic_policy(&self) -> PanicPolicy {
+        self.panic_policy
+    }
+    /// Cancellation token, if any.
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+    /// Scheduling priority of the worker threads.
+    pub fn priority(&self) -> ThreadPriority {
+        self.priority
+    }
+    /// Prefix of the worker thread names, if set.
+    pub fn thread_name_prefix(&self) -> Option<&str> {
+        self.thread_name_prefix.as_deref()
+    }
+    /// Stack size of the worker threads in bytes, if set.
+    pub fn stack_size(&self) -> Option<usize> {
+        self.stack_size
+    }
+    /// Size in bytes of the cache lines the sub-range boundaries are aligned
+    /// to, if set.
+    pub fn cache_line_size(&self) -> Option<usize> {
+        self.cache_line
+    }
+    /// Number of elements the sub-range offsets and lengths are multiples
+    /// of, if set.
+    pub fn simd_width(&self) -> Option<usize> {
+        self.simd_width
+    }
+    /// Maximum duration of a call, if set.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+    /// Whether the reductions are reproducible whatever the number of threads.
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+    /// Whether outputs may be delivered in completion order.
+    pub fn unordered(&self) -> bool {
+        self.unordered
+    }
+    // Executor of the workers, `None` for scoped threads.
+    pub(crate) fn spawner(&self) -> Option<&dyn Spawner> {
+        self.spawner.as_ref().map(|h| &*h.0)
+    }
+    // Destination of the statistics, `None` if they are not collected.
+    #[cfg(feature = "std")]
+    pub(crate) fn stats(&self) -> Option<&StatsCollector> {
+        self.stats.as_ref()
+    }
+    // Sub-ranges of a sequence of length `len` with elements of `elem_size`
+    // bytes, starting at address `addr`.
+    pub(crate) fn ranges(&self, len: usize, elem_size: usize, addr: usize) -> Vec<Range<usize>> {
+        // the ragged tail is split off into its own sub-range
+        let width = self.simd_width.unwrap_or(1);
+        let body = len - len % width;
+        let ranges = match (self.chunking, self.chunk_size, &self.weight) {
+            (Chunking::Guided, cs, _) => {
+                split::guided_ranges(body, self.num_threads, cs.unwrap_or(1))
+            }
+            (_, cs, Some(w)) => {
+                let parts = cs.map_or(self.num_threads(), |cs| body.div_ceil(cs.max(1)));
+                split::weighted_ranges(body, parts, &*w.0)
+            }
+            (_, Some(cs), None) => split::fixed_ranges(body, cs),
+            (_, None, None) => split::ranges(body, self.num_threads),
+        };
+        let (step, offset) = match self.cache_line {
+            Some(line) if elem_size > 0 => {
+                // smallest number of elements spanning whole lines, and
+                // first element starting a line, if any is a multiple of
+                // the SIMD width
+                let step = line / gcd(line, elem_size);
+                let step = step / gcd(step, width) * width;
+                let starts_line = |i: &usize| (addr + i * elem_size).is_multiple_of(line);
+                let offset = (0..step).step_by(width).find(starts_line);
+                (step, offset.unwrap_or(0))
+            }
+            _ => (width, 0),
+        };
+        let mut ranges = split::align_ranges(ranges, step, offset);
+        if body < len {
+            ranges.push(body..len);
+        }
+        ranges
+    }
+    // Same configuration with the sequences split into sub-ranges of
+    // `chunk_size` elements from their start, whatever the chunking.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn fixed_chunks(&self, chunk_size: usize) -> ParConfig {
+        ParConfig {
+            chunk_size: Some(chunk_size),
+            chunking: Chunking::Fixed,
+            weight: None,
+            simd_width: None,
+            cache_line: None,
+            ..self.clone()
+        }
+    }
+    // Cores worker `worker` is pinned to, if any.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn worker_cores(&self, worker: usize) -> Option<Vec<usize>> {
+        let nodes = self.numa_nodes.as_deref().unwrap_or_default();
+        match &self.cores {
+            Some(c) if !c.is_empty() => Some(vec![c[worker % c.len()]]),
+            // contiguous groups of workers share a node
+            _ if !nodes.is_empty() => {
+                let workers = self.num_threads().max(nodes.len());
+                Some(nodes[worker * nodes.len() / workers].clone())
+            }
+            _ if self.pin_threads => Some(vec![worker % crate::available_threads()]),
+            _ => None,
+        }
+    }
+    pub(crate) fn thread_start(&self, worker: usize) {
+        if let Some(h) = &self.on_thread_start {
+            (h.0)(worker)
+        }
+    }
+    pub(crate) fn thread_end(&self, worker: usize) {
+        if let Some(h) = &self.on_thread_end {
+            (h.0)(worker)
+        }
+    }
+    pub(crate) fn progress(&self, completed: usize, total: usize) {
+        if let Some(h) = &self.on_progress {
+            (h.0)(completed, total)
+        }
+    }
+    // Return `true` if the kernels must be timed.
+    #[cfg(feature = "std")]
+    pub(crate) fn timed(&self) -> bool {
+        self.stats.is_some() || self.on_chunk.is_some()
+    }
+    #[cfg(feature = "std")]
+    pub(crate) fn chunk_done(&self, worker: usize, index: usize, range: Range<usize>, t: Duration) {
+        if let Some(h) = &self.on_chunk {
+            (h.0)(worker, index, range, t)
+        }
+    }
+    // Instant past which a call started now times out.
+    #[cfg(feature = "std")]
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.timeout.map(|t| Instant::now() + t)
+    }
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        None
+    }
+    // Return `true` if the remaining sub-ranges must be skipped.
+    pub(crate) fn is_stopped(&self, deadline: Option<Instant>) -> bool {
+        self.cancel_token.as_ref().is_some_and(|t| t.is_cancelled()) || expired(deadline)
+    }
+    // Return `ParError::Cancelled` if the call was cancelled or
+    // `ParError::Timeout` if it did not complete before `deadline`.
+    pub(crate) fn check_stopped<E>(&self, deadline: Option<Instant>) -> Result<(), ParError<E>> {
+        if self.cancel_token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            Err(ParError::Cancelled)
+        } else if expired(deadline) {
+            Err(ParError::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(feature = "std")]
+fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|d| Instant::now() > d)
+}
+
+#[cfg(not(feature = "std"))]
+fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|d| match d {})
+}
+
+//-----------------------------------------------------------------------------
+/// Builder for [`ParConfig`].
+#[derive(Clone, Debug, Default)]
+pub struct ParConfigBuilder {
+    cfg: ParConfig,
+}
+
+impl ParConfigBuilder {
+    /// Set the number of threads to spawn, `0` (the default) uses all the
+    /// available hardware threads.
+    pub fn threads(mut self, num_threads: usize) -> Self {
+        self.cfg.num_threads = num_threads;
+        self
+    }
+    /// Split sequences into sub-ranges of `chunk_size` elements instead of one
+    /// sub-range per thread; the sub-ranges are fed to the threads from a
+    /// shared queue, balancing the load when sub-ranges take different times.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.cfg.chunk_size = Some(chunk_size);
+        self
+    }
+    /// Select how the sub-range sizes are chosen, see [`Chunking`].
+    pub fn chunking(mut self, chunking: Chunking) -> Self {
+        self.cfg.chunking = chunking;
+        self
+    }
+    /// Split sequences into sub-ranges of approximately equal total weight
+    /// instead of equal length, `weight` returning the cost of the element at
+    /// a given index; e.g. `kernel!(move |i| weights[i])` for precomputed
+    /// weights.
+    ///
+    /// There is one sub-range per thread, or as many as sub-ranges of the
+    /// chunk size if set. The weight of every element is computed twice on
+    /// the calling thread, it should be cheap compared to the kernel. Ignored
+    /// by [`Chunking::Guided`] and [`Chunking::Adaptive`].
+    pub fn weight_fn(mut self, weight: Arc<WeightFun>) -> Self {
+        self.cfg.weight = Some(Hook(weight));
+        self
+    }
+    /// Select how sub-ranges are assigned to the threads when there are more
+    /// sub-ranges than threads, see [`ParConfigBuilder::chunk_size`].
+    pub fn scheduling(mut self, scheduling: Scheduling) -> Self {
+        self.cfg.scheduling = scheduling;
+        self
+    }
+    /// Select how kernel panics are handled, see [`PanicPolicy`].
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.cfg.panic_policy = policy;
+        self
+    }
+    /// Skip the sub-ranges not yet started once `token` is cancelled.
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cfg.cancel_token = Some(token);
+        self
+    }
+    /// Call `hook` with the worker index on each worker thread before it runs
+    /// its first sub-range.
+    pub fn on_thread_start(mut self, hook: Arc<HookFun>) -> Self {
+        self.cfg.on_thread_start = Some(Hook(hook));
+        self
+    }
+    /// Call `hook` with the worker index on each worker thread once it has no
+    /// more sub-ranges to run.
+    pub fn on_thread_end(mut self, hook: Arc<HookFun>) -> Self {
+        self.cfg.on_thread_end = Some(Hook(hook));
+        self
+    }
+    /// Call `hook` with the number of completed sub-ranges and the total
+    /// number of sub-ranges each time a sub-range completes; called from the
+    /// worker threads, possibly concurrently.
+    pub fn on_progress(mut self, hook: Arc<ProgressFun>) -> Self {
+        self.cfg.on_progress = Some(Hook(hook));
+        self
+    }
+    /// Call `hook` with the worker index, the sub-range index, the element
+    /// range and the duration of the kernel each time a sub-range completes;
+    /// called from the worker threads.
+    ///
+    /// This is the place to forward the execution to a tracing or metrics
+    /// system, e.g. by emitting a `tracing` event per sub-range.
+    #[cfg(feature = "std")]
+    pub fn on_chunk(mut self, hook: Arc<ChunkHookFun>) -> Self {
+        self.cfg.on_chunk = Some(Hook(hook));
+        self
+    }
+    /// Skip the sub-ranges not yet started once the call has been running
+    /// for `timeout` and return [`ParError::Timeout`]; running kernels are not
+    /// interrupted, the threads are always joined before returning.
+    #[cfg(feature = "std")]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.cfg.timeout = Some(timeout);
+        self
+    }
+    /// Make the floating-point reductions taking a configuration, such as
+    /// [`par_sum_with`](crate::par_sum_with) or
+    /// [`par_dot_with`](crate::par_dot_with), reproducible bit for bit
+    /// whatever the number of threads, scheduling and chunking: the sequence
+    /// is reduced by sub-ranges of a fixed size whose results are combined
+    /// pairwise, in sequence order, along a tree depending only on the
+    /// length.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.cfg.deterministic = deterministic;
+        self
+    }
+    /// Let the calls delivering outputs while they run, such as
+    /// [`par_stream_with`](crate::par_stream_with), deliver each output as
+    /// soon as it completes instead of holding it back until the outputs of
+    /// the earlier inputs are delivered; `false`, the default, restores the
+    /// input order.
+    pub fn unordered(mut self, unordered: bool) -> Self {
+        self.cfg.unordered = unordered;
+        self
+    }
+    /// Measure the chunks run, busy time and wait time of every worker and
+    /// record them into `collector` at the end of each call.
+    #[cfg(feature = "std")]
+    pub fn stats(mut self, collector: StatsCollector) -> Self {
+        self.cfg.stats = Some(collector);
+        self
+    }
+    /// Pin worker `i` to core `i % available_threads()`, preventing the OS from
+    /// migrating the workers; ignored on platforms other than Linux.
+    pub fn pin_threads(mut self, pin: bool) -> Self {
+        self.cfg.pin_threads = pin;
+        self
+    }
+    /// Run the workers at `priority`, e.g. [`ThreadPriority::Background`] for
+    /// batch jobs which must not slow down interactive threads; the calling
+    /// thread is not affected.
+    pub fn priority(mut self, priority: ThreadPriority) -> Self {
+        self.cfg.priority = priority;
+        self
+    }
+    /// Pin worker `i` to core `cores[i % cores.len()]`, see
+    /// [`ParConfigBuilder::pin_threads`].
+    pub fn cores(mut self, cores: Vec<usize>) -> Self {
+        self.cfg.cores = Some(cores);
+        self
+    }
+    /// Bind the workers to NUMA nodes, `nodes[i]` listing the cores of node
+    /// `i`: consecutive workers are bound to the same node, whatever the
+    /// scheduling. Combined with [`Scheduling::Static`], in any order, each
+    /// worker always processes the same contiguous part of a sequence, so
+    /// that pages first touched through the same configuration stay local to
+    /// the node of the worker accessing them.
+    ///
+    /// ```rust,ignore
+    /// let cfg = ParConfig::builder()
+    ///     .numa_aware()
+    ///     .scheduling(Scheduling::Static)
+    ///     .build();
+    /// ```
+    pub fn numa_nodes(mut self, nodes: Vec<Vec<usize>>) -> Self {
+        self.cfg.numa_nodes = Some(nodes);
+        self
+    }
+    /// Same as [`ParConfigBuilder::numa_nodes`] with the topology detected by
+    /// [`numa_nodes`](crate::numa_nodes); no-op if it cannot be detected.
+    #[cfg(feature = "std")]
+    pub fn numa_aware(self) -> Self {
+        match crate::numa_nodes() {
+            nodes if nodes.is_empty() => self,
+            nodes => self.numa_nodes(nodes),
+        }
+    }
+    /// Name worker `i` `"{prefix}-{i}"`, visible in debuggers and profilers.
+    pub fn thread_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.cfg.thread_name_prefix = Some(prefix.into());
+        self
+    }
+    /// Spawn the worker threads with a stack of `size` bytes instead of the
+    /// platform default, for deeply recursive kernels.
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.cfg.stack_size = Some(size);
+        self
+    }
+    /// Move the boundaries between sub-ranges to addresses multiple of 64
+    /// bytes, the most common cache line size, so that threads writing
+    /// adjacent sub-ranges of small elements never share a cache line; for
+    /// two sequences such as the source and destination of a map, the
+    /// destination is aligned.
+    pub fn cache_aligned(mut self, aligned: bool) -> Self {
+        self.cfg.cache_line = aligned.then_some(CACHE_LINE_SIZE);
+        self
+    }
+    /// Same as [`ParConfigBuilder::cache_aligned`] with cache lines of `size`
+    /// bytes.
+    pub fn cache_line_size(mut self, size: usize) -> Self {
+        self.cfg.cache_line = Some(size.max(1));
+        self
+    }
+    /// Make the offset and length of every sub-range multiples of `width`
+    /// elements, e.g. the number of lanes of an explicitly vectorized kernel,
+    /// except for a final sub-range holding the remaining elements.
+    pub fn simd_width(mut self, width: usize) -> Self {
+        self.cfg.simd_width = Some(width.max(1));
+        self
+    }
+    /// Run the workers on `spawner` instead of spawning a scoped thread per
+    /// worker; the thread name, stack size and core pinning options then have
+    /// no effect.
+    pub fn spawner(mut self, spawner: Arc<dyn Spawner>) -> Self {
+        self.cfg.spawner = Some(Hook(spawner));
+        self
+    }
+    /// Return the configuration.
+    pub fn build(self) -> ParConfig {
+        self.cfg
+    }
+}
@@ -0,0 +1,70 @@
+//! Kernels receiving a shared read-only context.
+
+use crate::{exec, split, ParError};
+use std::sync::Arc;
+
+type CtxKernelFun2<'a, C, T> = dyn Fn(&C, &[T], &mut [T]) + Send + Sync + 'a;
+type CtxKernelFun1<'a, C, T> = dyn Fn(&C, &mut [T]) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`](crate::par_map), the kernel also receives a reference
+/// to `ctx`, shared by all the threads: `Fn(&C, &[T], &mut [T])`.
+///
+/// Lookup tables and parameters can be passed here instead of being captured
+/// by the kernel.
+pub fn par_map_ctx<T: Send + Sync, C: Send + Sync>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    ctx: Arc<C>,
+    fr: Arc<CtxKernelFun2<C, T>>,
+) -> Result<(), ParError> {
+    crate::check_len(src.len(), dest.len())?;
+    exec::run_chunks(
+        num_threads,
+        split::chunks((src, dest), num_threads),
+        |(_, (s, d))| fr(&ctx, s, d),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`](crate::par_in_place_map) with a shared
+/// context, see [`par_map_ctx`]: `Fn(&C, &mut [T])`.
+pub fn par_in_place_map_ctx<T: Send, C: Send + Sync>(
+    dest: &mut [T],
+    num_threads: usize,
+    ctx: Arc<C>,
+    fr: Arc<CtxKernelFun1<C, T>>,
+) -> Result<(), ParError> {
+    exec::run_chunks(num_threads, split::chunks(dest, num_threads), |(_, d)| {
+        fr(&ctx, d)
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_map_ctx_test() -> Result<(), ParError> {
+        // gamma lookup table
+        let lut: Arc<Vec<u8>> = Arc::new((0..=255).map(|i: u16| (i * i / 255) as u8).collect());
+        let src: Vec<u8> = (0..=255).collect();
+        let mut dest = vec![0; src.len()];
+        let kernel_fun = |lut: &Vec<u8>, s: &[u8], d: &mut [u8]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = lut[usize::from(*s)];
+            }
+        };
+        par_map_ctx(&src, &mut dest, 4, lut.clone(), kernel!(kernel_fun))?;
+        assert_eq!(dest, *lut);
+        let kernel_fun = |offset: &u8, d: &mut [u8]| d.iter_mut().for_each(|e| *e = *offset);
+        par_in_place_map_ctx(&mut dest, 3, Arc::new(9), kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 9));
+        Ok(())
+    }
+}
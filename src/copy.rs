@@ -0,0 +1,118 @@
+//! Parallel copy and fill.
+
+use crate::{exec, split, ParError};
+
+// Minimum number of bytes per thread: below it spawning costs more than the
+// copy itself.
+const MIN_BYTES_PER_THREAD: usize = 256 << 10;
+
+// Number of threads worth spawning to write `len` elements of type `T`.
+fn copy_threads<T>(len: usize, num_threads: usize) -> usize {
+    let bytes = len.saturating_mul(core::mem::size_of::<T>().max(1));
+    crate::resolve_threads(num_threads).min((bytes / MIN_BYTES_PER_THREAD).max(1))
+}
+
+//-----------------------------------------------------------------------------
+/// Copy `src` into `dest`.
+///
+/// Sequences shorter than a few hundred KiB per thread are copied by fewer
+/// threads, down to a plain copy on the calling thread. Returns
+/// [`ParError::LengthMismatch`] if `src` and `dest` have different lengths.
+pub fn par_copy<T: Copy + Send + Sync>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    crate::check_len(src.len(), dest.len())?;
+    let threads = copy_threads::<T>(src.len(), num_threads);
+    if threads == 1 {
+        dest.copy_from_slice(src);
+        return Ok(());
+    }
+    exec::run_chunks(
+        threads,
+        split::chunks((src, dest), threads),
+        |(_, (s, d))| d.copy_from_slice(s),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_copy`] for elements which are `Clone` only.
+pub fn par_clone_from<T: Clone + Send + Sync>(
+    dest: &mut [T],
+    src: &[T],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    crate::check_len(src.len(), dest.len())?;
+    let threads = copy_threads::<T>(src.len(), num_threads);
+    if threads == 1 {
+        dest.clone_from_slice(src);
+        return Ok(());
+    }
+    exec::run_chunks(
+        threads,
+        split::chunks((src, dest), threads),
+        |(_, (s, d))| d.clone_from_slice(s),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Set every element of `dest` to a clone of `value`, see [`par_copy`].
+pub fn par_fill<T: Clone + Send + Sync>(
+    dest: &mut [T],
+    value: T,
+    num_threads: usize,
+) -> Result<(), ParError> {
+    let threads = copy_threads::<T>(dest.len(), num_threads);
+    if threads == 1 {
+        dest.fill(value);
+        return Ok(());
+    }
+    let value = &value;
+    exec::run_chunks(threads, split::chunks(dest, threads), |(_, d)| {
+        d.fill(value.clone())
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    #[test]
+    fn par_copy_test() -> Result<(), ParError> {
+        assert_eq!(copy_threads::<u8>(1000, 8), 1);
+        assert_eq!(copy_threads::<u64>(1 << 20, 4), 4);
+        let src: Vec<u32> = (0..1 << 20).collect();
+        let mut dest = vec![0_u32; src.len()];
+        par_copy(&src, &mut dest, 4)?;
+        assert_eq!(dest, src);
+        par_fill(&mut dest, 7, 4)?;
+        assert!(dest.iter().all(|e| *e == 7));
+        let mut small = vec![0_u32; 10];
+        par_copy(&src[..10], &mut small, 4)?;
+        assert_eq!(small, &src[..10]);
+        for dest in [&mut small[..], &mut dest[..]] {
+            let r = par_copy(&src[..20], dest, 4);
+            assert!(matches!(r, Err(ParError::LengthMismatch { src: 20, .. })));
+        }
+        let strings: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let mut dest = vec![String::new(); 100];
+        par_clone_from(&mut dest, &strings, 3)?;
+        assert_eq!(dest, strings);
+        let r = par_clone_from(&mut dest[..50], &strings, 3);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 100, dest: 50 })
+        ));
+        par_fill(&mut dest, "x".to_string(), 3)?;
+        assert!(dest.iter().all(|e| e == "x"));
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Distinct elements.
+
+use crate::{exec, split, ParError};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash};
+
+//-----------------------------------------------------------------------------
+/// Number of distinct elements.
+///
+/// Each thread inserts the elements of its sub-range into one private
+/// `HashSet` per hash partition; the sets of each partition are then merged
+/// by their own thread, elements of different partitions being always
+/// distinct, so that no single thread ever merges all the sets.
+pub fn par_unique_count<T: Hash + Eq + Sync>(
+    src: &[T],
+    num_threads: usize,
+) -> Result<usize, ParError> {
+    let chunks = split::chunks(src, num_threads);
+    let parts = chunks.len();
+    let state = RandomState::new();
+    let state = &state;
+    let sets = exec::run_chunks(num_threads, chunks, |(_, s)| {
+        let mut sets: Vec<HashSet<&T, RandomState>> = (0..parts)
+            .map(|_| HashSet::with_hasher(state.clone()))
+            .collect();
+        for e in s {
+            sets[(state.hash_one(e) % parts as u64) as usize].insert(e);
+        }
+        sets
+    })?;
+    // partition `p` of every sub-range
+    let mut partitions: Vec<Vec<HashSet<&T, RandomState>>> =
+        (0..parts).map(|_| Vec::with_capacity(parts)).collect();
+    for sets in sets {
+        for (p, set) in partitions.iter_mut().zip(sets) {
+            p.push(set);
+        }
+    }
+    let counts = exec::run(num_threads, partitions, |mut sets| {
+        // merge into the largest set
+        sets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        let mut sets = sets.into_iter();
+        let mut merged = sets.next().unwrap_or_default();
+        for s in sets {
+            merged.extend(s);
+        }
+        merged.len()
+    })?;
+    Ok(counts.into_iter().sum())
+}
+
+//-----------------------------------------------------------------------------
+/// Remove consecutive repeated elements, same as `Vec::dedup` on a copy of
+/// `src`; on a sorted sequence the result holds each distinct element once.
+///
+/// Each thread deduplicates its own sub-range, then the first element of a
+/// sub-range is dropped if equal to the last element of the previous one.
+pub fn par_dedup_sorted<T: PartialEq + Clone + Send + Sync>(
+    src: &[T],
+    num_threads: usize,
+) -> Result<Vec<T>, ParError> {
+    let runs = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| {
+        let mut d = s.to_vec();
+        d.dedup();
+        d
+    })?;
+    let mut dest: Vec<T> = Vec::with_capacity(runs.iter().map(Vec::len).sum());
+    for run in runs {
+        let skip = usize::from(dest.last().is_some_and(|l| run.first() == Some(l)));
+        dest.extend(run.into_iter().skip(skip));
+    }
+    Ok(dest)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn par_unique_count_test() -> Result<(), ParError> {
+        let src: Vec<u64> = (0..100_000).map(|i| (i * 7919) % 12_345).collect();
+        for n in [1, 3, 8] {
+            assert_eq!(par_unique_count(&src, n)?, 12_345);
+        }
+        let words = ["a", "b", "a", "c", "b", "a"];
+        assert_eq!(par_unique_count(&words, 4)?, 3);
+        assert_eq!(par_unique_count::<u8>(&[], 4)?, 0);
+        Ok(())
+    }
+    #[test]
+    fn par_dedup_sorted_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1000).map(|i| i / 3).collect();
+        let expected: Vec<u32> = (0..334).collect();
+        for n in [1, 4, 7] {
+            assert_eq!(par_dedup_sorted(&src, n)?, expected);
+        }
+        assert_eq!(par_dedup_sorted(&[1, 1, 2, 1, 1], 2)?, [1, 2, 1]);
+        Ok(())
+    }
+}
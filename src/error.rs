@@ -0,0 +1,298 @@
+//! Errors returned by the parallel functions.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use core::ops::Range;
+
+//-----------------------------------------------------------------------------
+/// Error returned by a kernel on a specific sub-range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkError<E> {
+    /// Index of the failed sub-range.
+    pub index: usize,
+    /// Element range of the failed sub-range in the sequence.
+    pub range: Range<usize>,
+    /// Error returned by the kernel.
+    pub error: E,
+}
+
+//-----------------------------------------------------------------------------
+/// Index past the end of the indexed sequence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexOutOfBounds {
+    /// Position of the index in the index array.
+    pub position: usize,
+    /// Value of the index.
+    pub index: usize,
+    /// Length of the indexed sequence.
+    pub len: usize,
+}
+
+impl fmt::Display for IndexOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "index {} at position {} out of bounds for length {}",
+            self.index, self.position, self.len
+        )
+    }
+}
+
+impl core::error::Error for IndexOutOfBounds {}
+
+//-----------------------------------------------------------------------------
+/// Error of [`par_scatter`](crate::par_scatter).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScatterError {
+    /// Index past the end of the destination.
+    OutOfBounds(IndexOutOfBounds),
+    /// Destination element written more than once under
+    /// [`Conflict::Error`](crate::Conflict::Error).
+    Conflict {
+        /// Index of the destination element.
+        index: usize,
+        /// Positions in the index array of the first two writes.
+        positions: (usize, usize),
+    },
+}
+
+impl fmt::Display for ScatterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScatterError::OutOfBounds(e) => write!(f, "{e}"),
+            ScatterError::Conflict { index, positions } => write!(
+                f,
+                "index {index} written at positions {} and {}",
+                positions.0, positions.1
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ScatterError {}
+
+//-----------------------------------------------------------------------------
+/// Error of [`par_decode_chunks`](crate::par_decode_chunks), reported for
+/// the failed frames; the range of a frame is its byte range in the
+/// container.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodecError<E> {
+    /// Truncated container or inconsistent frame table, reported for the
+    /// whole container.
+    Malformed,
+    /// Error returned by the codec.
+    Codec(E),
+    /// Frame decoded to a length other than the one recorded when encoding.
+    Length {
+        /// Recorded length.
+        expected: usize,
+        /// Decoded length.
+        actual: usize,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for CodecError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Malformed => f.write_str("malformed container"),
+            CodecError::Codec(e) => write!(f, "{e}"),
+            CodecError::Length { expected, actual } => {
+                write!(f, "frame decoded to {actual} bytes instead of {expected}")
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for CodecError<E> {}
+
+//-----------------------------------------------------------------------------
+/// Panic of a kernel on a specific sub-range.
+pub struct ChunkPanic {
+    /// Index of the sub-range, `None` if the panic did not happen in a kernel,
+    /// e.g. in a thread hook.
+    pub index: Option<usize>,
+    /// Element range of the sub-range in the sequence, when known.
+    pub range: Option<Range<usize>>,
+    /// Panic message, when the payload is a string.
+    pub message: Option<String>,
+    /// Payload passed to `panic!`.
+    pub payload: Box<dyn Any + Send + 'static>,
+}
+
+impl ChunkPanic {
+    pub(crate) fn new(
+        index: Option<usize>,
+        range: Option<Range<usize>>,
+        payload: Box<dyn Any + Send + 'static>,
+    ) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned());
+        ChunkPanic {
+            index,
+            range,
+            message,
+            payload,
+        }
+    }
+}
+
+impl fmt::Debug for ChunkPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkPanic")
+            .field("index", &self.index)
+            .field("range", &self.range)
+            .field("message", &self.message)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for ChunkPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(i) => write!(f, "chunk {i}")?,
+            None => write!(f, "worker thread")?,
+        }
+        if let Some(r) = &self.range {
+            write!(f, " {r:?}")?;
+        }
+        write!(f, " panicked")?;
+        if let Some(m) = &self.message {
+            write!(f, ": {m}")?;
+        }
+        Ok(())
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Panics raised during a parallel call.
+#[derive(Debug)]
+pub struct PanicError {
+    /// The panics, sorted by sub-range index; only the ones which happened
+    /// before the other workers stopped under
+    /// [`PanicPolicy::FailFast`](crate::PanicPolicy::FailFast).
+    pub panics: Vec<ChunkPanic>,
+    /// Number of sub-ranges which completed successfully.
+    pub completed: usize,
+    /// Total number of sub-ranges.
+    pub total: usize,
+}
+
+impl PanicError {
+    /// First panic, by sub-range index.
+    pub fn first(&self) -> &ChunkPanic {
+        &self.panics[0]
+    }
+    /// Message of the first panic, when available.
+    pub fn message(&self) -> Option<&str> {
+        self.first().message.as_deref()
+    }
+    /// Payload of the first panic, e.g. to pass to
+    /// `std::panic::resume_unwind`.
+    pub fn into_payload(mut self) -> Box<dyn Any + Send + 'static> {
+        self.panics.swap_remove(0).payload
+    }
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first())?;
+        if self.panics.len() > 1 {
+            write!(f, " (+{} more)", self.panics.len() - 1)?;
+        }
+        write!(
+            f,
+            ", {} of {} chunk(s) completed",
+            self.completed, self.total
+        )
+    }
+}
+
+impl core::error::Error for PanicError {}
+
+//-----------------------------------------------------------------------------
+/// Error returned by the fallible parallel functions.
+pub enum ParError<E = core::convert::Infallible> {
+    /// One or more kernels returned an error, sorted by sub-range index.
+    Kernel(Vec<ChunkError<E>>),
+    /// One or more kernels panicked.
+    Panic(PanicError),
+    /// The call was cancelled through a [`CancelToken`](crate::CancelToken).
+    Cancelled,
+    /// The call did not complete within the configured timeout.
+    Timeout,
+    /// The source and destination sequences have different lengths.
+    LengthMismatch {
+        /// Length of the source.
+        src: usize,
+        /// Length of the destination.
+        dest: usize,
+    },
+    /// The source and destination refer to the same memory.
+    Overlap,
+    /// An I/O operation failed before or after the parallel part of the call.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl<E> From<PanicError> for ParError<E> {
+    fn from(e: PanicError) -> Self {
+        ParError::Panic(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E> From<std::io::Error> for ParError<E> {
+    fn from(e: std::io::Error) -> Self {
+        ParError::Io(e)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for ParError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParError::Kernel(errors) => f.debug_tuple("Kernel").field(errors).finish(),
+            ParError::Panic(e) => f.debug_tuple("Panic").field(e).finish(),
+            ParError::Cancelled => write!(f, "Cancelled"),
+            ParError::Timeout => write!(f, "Timeout"),
+            ParError::LengthMismatch { src, dest } => f
+                .debug_struct("LengthMismatch")
+                .field("src", src)
+                .field("dest", dest)
+                .finish(),
+            ParError::Overlap => write!(f, "Overlap"),
+            #[cfg(feature = "std")]
+            ParError::Io(e) => f.debug_tuple("Io").field(e).finish(),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ParError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParError::Kernel(errors) => {
+                write!(f, "{} chunk(s) failed", errors.len())?;
+                if let Some(e) = errors.first() {
+                    write!(f, ", first: chunk {} {:?}: {}", e.index, e.range, e.error)?;
+                }
+                Ok(())
+            }
+            ParError::Panic(e) => write!(f, "{e}"),
+            ParError::Cancelled => write!(f, "cancelled"),
+            ParError::Timeout => write!(f, "timed out"),
+            ParError::LengthMismatch { src, dest } => write!(
+                f,
+                "length mismatch: source has {src} elements, destination {dest}"
+            ),
+            ParError::Overlap => write!(f, "source and destination overlap"),
+            #[cfg(feature = "std")]
+            ParError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for ParError<E> {}
@@ -0,0 +1,757 @@
+//! Execution of jobs on worker threads.
+
+use crate::split::{self, Split};
+use crate::{ChunkPanic, Job, PanicError, ParConfig};
+#[cfg(feature = "std")]
+use crate::{ParStats, WorkerStats};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use std::cell::Cell;
+#[cfg(feature = "std")]
+use std::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "std")]
+use std::thread::{Scope, ScopedJoinHandle};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+//-----------------------------------------------------------------------------
+// Join all the threads, returning the results in order or the first error;
+// every handle is joined so that the enclosing scope never re-raises a panic.
+#[cfg(feature = "std")]
+fn join_all<R>(
+    th: impl ExactSizeIterator<Item = std::thread::Result<R>>,
+) -> std::thread::Result<Vec<R>> {
+    let mut results = Vec::with_capacity(th.len());
+    let mut err = None;
+    for t in th {
+        match t {
+            Ok(r) => results.push(r),
+            Err(e) => {
+                err.get_or_insert(e);
+            }
+        }
+    }
+    match err {
+        Some(e) => Err(e),
+        None => Ok(results),
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Assignment of the sub-ranges to the threads when there are more sub-ranges
+/// than threads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheduling {
+    /// Threads pull the sub-ranges in order from a single shared queue.
+    #[default]
+    Shared,
+    /// Each thread processes a fixed contiguous group of sub-ranges.
+    Static,
+    /// Each thread starts with a contiguous group of sub-ranges and, once
+    /// done, steals the remaining sub-ranges from the end of the groups of the
+    /// other threads.
+    Dynamic,
+    /// Sub-ranges are dealt round-robin: thread `i` processes sub-ranges
+    /// `i, i + n, i + 2n, ..` for `n` threads. With a small chunk size the
+    /// threads get an even share of every part of the sequence, balancing
+    /// the load when the cost of an element grows with its position, e.g.
+    /// the rows of a triangular matrix.
+    Cyclic,
+}
+
+//-----------------------------------------------------------------------------
+/// Sizing of the sub-ranges, see [`ParConfigBuilder::chunking`](crate::ParConfigBuilder::chunking).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Chunking {
+    /// Sub-ranges of the chunk size, or one sub-range per thread if no chunk
+    /// size is set.
+    #[default]
+    Fixed,
+    /// Sub-ranges split off while the call runs: the first ones have the
+    /// chunk size, or 1/16 of the elements per thread if none is set, and the
+    /// following ones are sized from the measured throughput of the kernel so
+    /// that each runs for about 100 µs, never exceeding half of the remaining
+    /// elements per thread. Without `std`, same as `Fixed`.
+    Adaptive,
+    /// Decreasing sub-range sizes as in OpenMP `schedule(guided)`: each
+    /// sub-range holds the remaining elements divided by the number of
+    /// threads, down to the chunk size, or one element if none is set. The
+    /// large early sub-ranges keep the scheduling overhead low while the
+    /// small final ones even out the completion times; best combined with
+    /// [`Scheduling::Shared`], which hands them out in order.
+    Guided,
+}
+
+//-----------------------------------------------------------------------------
+/// Handling of kernel panics, and of kernel errors in
+/// [`par_try_map_with`](crate::par_try_map_with), see
+/// [`ParConfigBuilder::panic_policy`](crate::ParConfigBuilder::panic_policy).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// After the first panic or error the workers stop starting new
+    /// sub-ranges.
+    #[default]
+    FailFast,
+    /// All the sub-ranges are run and every panic is collected in the
+    /// returned [`PanicError`], every error in
+    /// [`ParError::Kernel`](crate::ParError::Kernel).
+    CollectAll,
+}
+
+//-----------------------------------------------------------------------------
+/// Scheduling priority of the worker threads, see
+/// [`ParConfigBuilder::priority`](crate::ParConfigBuilder::priority).
+///
+/// Mapped to the nice value on Linux, the QoS class on macOS and the thread
+/// priority on Windows; ignored on other platforms and for workers run by a
+/// [`Spawner`](crate::Spawner).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// Priority inherited from the calling thread.
+    #[default]
+    Normal,
+    /// Below normal: nice value 10, `QOS_CLASS_UTILITY`,
+    /// `THREAD_PRIORITY_BELOW_NORMAL`.
+    Low,
+    /// Only runs when the system is otherwise idle: nice value 19,
+    /// `QOS_CLASS_BACKGROUND`, `THREAD_PRIORITY_LOWEST`.
+    Background,
+}
+
+//-----------------------------------------------------------------------------
+// Run `f` on each part on at most `num_threads` scoped threads, collecting
+// the returned values in part order.
+pub(crate) fn run<P, R, F>(num_threads: usize, parts: Vec<P>, f: F) -> Result<Vec<R>, PanicError>
+where
+    P: Send,
+    R: Send,
+    F: Fn(P) -> R + Sync,
+{
+    let parts = parts.into_iter().map(|p| (None, p)).collect();
+    run_workers(
+        &Call::new(num_threads),
+        parts,
+        |_| (),
+        |_, p| f(p),
+        |_, _| (),
+    )
+}
+
+// Same as `run` on the chunks returned by `split::chunks`, a panic is
+// reported with the element range of its chunk.
+pub(crate) fn run_chunks<P, R, F>(
+    num_threads: usize,
+    chunks: Vec<(Range<usize>, P)>,
+    f: F,
+) -> Result<Vec<R>, PanicError>
+where
+    P: Send,
+    R: Send,
+    F: Fn((Range<usize>, P)) -> R + Sync,
+{
+    let parts = with_ranges(chunks);
+    run_workers(
+        &Call::new(num_threads),
+        parts,
+        |_| (),
+        |_, p| f(p),
+        |_, _| (),
+    )
+}
+
+// Same as `run_chunks` on `data` split into the sub-ranges selected by `cfg`,
+// with the threads, scheduling, hooks and panic policy taken from `cfg`.
+pub(crate) fn run_with<P, R, F>(cfg: &ParConfig, data: P, f: F) -> Result<Vec<R>, PanicError>
+where
+    P: Split,
+    R: Send,
+    F: Fn((Range<usize>, P)) -> R + Sync,
+{
+    let call = Call {
+        num_threads: cfg.num_threads(),
+        scheduling: cfg.scheduling(),
+        policy: cfg.panic_policy(),
+        cfg: Some(cfg),
+    };
+    let init = |w| cfg.thread_start(w);
+    let f = |_: &mut (), p| f(p);
+    let fini = |w, _| cfg.thread_end(w);
+    #[cfg(feature = "std")]
+    if cfg.chunking() == Chunking::Adaptive {
+        let workers = worker_count(cfg.num_threads(), data.len());
+        let source = Adaptive::new(data, workers, cfg.chunk_size());
+        return run_source(&call, &source, workers, init, f, fini);
+    }
+    let chunks = split::chunks_with(cfg, data);
+    run_workers(&call, with_ranges(chunks), init, f, fini)
+}
+
+// Same as `run_chunks` with one state per worker thread created by `init`,
+// which receives the worker index, and passed to every job run by the worker.
+pub(crate) fn run_stateful<P, R, S, I, F>(
+    num_threads: usize,
+    chunks: Vec<(Range<usize>, P)>,
+    init: I,
+    f: F,
+) -> Result<Vec<R>, PanicError>
+where
+    P: Send,
+    R: Send,
+    I: Fn(usize) -> S + Sync,
+    F: Fn(&mut S, (Range<usize>, P)) -> R + Sync,
+{
+    let parts = with_ranges(chunks);
+    run_workers(&Call::new(num_threads), parts, init, f, |_, _| ())
+}
+
+fn with_ranges<P>(chunks: Vec<(Range<usize>, P)>) -> Vec<Part<(Range<usize>, P)>> {
+    chunks
+        .into_iter()
+        .map(|(r, p)| (Some(r.clone()), (r, p)))
+        .collect()
+}
+
+// Part with the element range reported when it panics.
+type Part<P> = (Option<Range<usize>>, P);
+
+// Parameters of a call.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+struct Call<'a> {
+    num_threads: usize,
+    scheduling: Scheduling,
+    policy: PanicPolicy,
+    // source of the progress callback
+    cfg: Option<&'a ParConfig>,
+}
+
+impl Call<'_> {
+    fn new(num_threads: usize) -> Self {
+        Call {
+            num_threads,
+            scheduling: Scheduling::default(),
+            policy: PanicPolicy::default(),
+            cfg: None,
+        }
+    }
+}
+
+// The parts are placed in queues from which the workers pull them according
+// to the scheduling, see `run_source`.
+#[cfg(feature = "std")]
+fn run_workers<P, R, S, I, F, D>(
+    call: &Call,
+    parts: Vec<Part<P>>,
+    init: I,
+    f: F,
+    fini: D,
+) -> Result<Vec<R>, PanicError>
+where
+    P: Send,
+    R: Send,
+    I: Fn(usize) -> S + Sync,
+    F: Fn(&mut S, P) -> R + Sync,
+    D: Fn(usize, S) + Sync,
+{
+    let workers = worker_count(call.num_threads, parts.len());
+    let queues = Queues::new(call.scheduling, workers, parts);
+    run_source(call, &queues, workers, init, f, fini)
+}
+
+// Number of workers for `len` parts, a single one for nested calls.
+#[cfg(feature = "std")]
+fn worker_count(num_threads: usize, len: usize) -> usize {
+    if IN_WORKER.get() {
+        len.min(1)
+    } else {
+        crate::resolve_threads(num_threads).min(len)
+    }
+}
+
+// Each of the `workers` creates its state with `init`, pulls parts from
+// `source` until it is exhausted and hands its state to `fini`.
+//
+// The panics of `f` are caught so that, depending on the panic policy, the
+// other workers stop starting new parts or keep running.
+#[cfg(feature = "std")]
+fn run_source<P, R, S, I, F, D>(
+    call: &Call,
+    source: &impl Source<P>,
+    workers: usize,
+    init: I,
+    f: F,
+    fini: D,
+) -> Result<Vec<R>, PanicError>
+where
+    P: Send,
+    R: Send,
+    I: Fn(usize) -> S + Sync,
+    F: Fn(&mut S, P) -> R + Sync,
+    D: Fn(usize, S) + Sync,
+{
+    let completed = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let panics = Mutex::new(Vec::new());
+    let fail_fast = call.policy == PanicPolicy::FailFast;
+    // kernels are only timed when the statistics are collected
+    let collector = call.cfg.and_then(|c| c.stats());
+    let start = call.cfg.is_some_and(|c| c.timed()).then(Instant::now);
+    let counters: Vec<Mutex<WorkerStats>> = match collector {
+        Some(_) => (0..workers).map(|_| Mutex::default()).collect(),
+        None => Vec::new(),
+    };
+    let job = |state: &mut S, w: usize, i: usize, (range, p): Part<P>| {
+        if fail_fast && stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let t = start.map(|_| Instant::now());
+        if let Some(collector) = collector {
+            collector.chunk_started();
+        }
+        let r = panic::catch_unwind(AssertUnwindSafe(|| f(state, p)));
+        if let Some(t) = t {
+            let t = t.elapsed();
+            if let Some(c) = counters.get(w) {
+                let mut c = lock(c);
+                c.chunks += 1;
+                c.busy += t;
+            }
+            if let Some(collector) = collector {
+                collector.chunk_done(t);
+            }
+            if let (Some(cfg), Some(range)) = (call.cfg, &range) {
+                cfg.chunk_done(w, i, range.clone(), t);
+            }
+        }
+        match r {
+            Ok(r) => {
+                let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(cfg) = call.cfg {
+                    cfg.progress(done, source.total());
+                }
+                Some(r)
+            }
+            Err(payload) => {
+                stop.store(true, Ordering::Relaxed);
+                lock(&panics).push(ChunkPanic::new(Some(i), range, payload));
+                None
+            }
+        }
+    };
+    let (init, fini) = (&init, &fini);
+    let worker = |w: usize| {
+        let mut state = init(w);
+        let mut done = Vec::new();
+        // the lock guard is dropped before running the job
+        while let Some((i, p)) = source.next(w) {
+            done.push((i, job(&mut state, w, i, p)));
+        }
+        fini(w, state);
+        done
+    };
+    let worker = &worker;
+    let done = if workers == 0 {
+        // empty input, nothing to run on either path
+        Ok(vec![])
+    } else if IN_WORKER.get() {
+        // nested call from a kernel: the outer call already occupies the
+        // threads, run the parts serially on the calling worker
+        Ok(vec![worker(0)])
+    } else {
+        launch(call.cfg, (0..workers).map(|w| move || worker(w)).collect())
+    };
+    let num_parts = source.total();
+    if let (Some(collector), Some(start)) = (collector, start) {
+        let elapsed = start.elapsed();
+        let workers = counters
+            .into_iter()
+            .map(|c| {
+                let mut c = c.into_inner().unwrap_or_else(|e| e.into_inner());
+                c.wait = elapsed.saturating_sub(c.busy);
+                c
+            })
+            .collect();
+        collector.record(ParStats { elapsed, workers });
+    }
+    let mut panics = panics.into_inner().unwrap_or_else(|e| e.into_inner());
+    match done {
+        // a worker panicked outside of `f`
+        Err(payload) => panics.push(ChunkPanic::new(None, None, payload)),
+        Ok(done) if panics.is_empty() => {
+            let mut results: Vec<Option<R>> = (0..num_parts).map(|_| None).collect();
+            for (i, r) in done.into_iter().flatten() {
+                results[i] = r;
+            }
+            return Ok(results.into_iter().flatten().collect());
+        }
+        Ok(_) => {}
+    }
+    panics.sort_by_key(|p| p.index.unwrap_or(usize::MAX));
+    Err(PanicError {
+        panics,
+        completed: completed.into_inner(),
+        total: num_parts,
+    })
+}
+
+// Run the workers on the spawner of `cfg`, or on scoped threads if none is
+// set, returning their results in order.
+#[cfg(feature = "std")]
+fn launch<R, J>(cfg: Option<&ParConfig>, jobs: Vec<J>) -> std::thread::Result<Vec<R>>
+where
+    R: Send,
+    J: FnOnce() -> R + Send,
+{
+    let Some(spawner) = cfg.and_then(|c| c.spawner()) else {
+        let jobs: Vec<_> = jobs.into_iter().map(|j| Mutex::new(Some(j))).collect();
+        return std::thread::scope(|s| {
+            let th: Vec<_> = jobs
+                .iter()
+                .enumerate()
+                .map(|(w, j)| spawn(s, cfg, w, move || run_slot(j)).ok())
+                .collect();
+            // without thread support, e.g. on wasm32-unknown-unknown, the
+            // jobs whose thread could not be spawned run on the calling thread
+            let serial: Vec<_> = th
+                .iter()
+                .zip(&jobs)
+                .map(|(t, j)| {
+                    t.is_none().then(|| {
+                        as_worker(|| panic::catch_unwind(AssertUnwindSafe(|| run_slot(j))))
+                    })
+                })
+                .collect();
+            join_all(th.into_iter().zip(serial).map(|(t, r)| match t {
+                Some(t) => t.join(),
+                None => r.expect("job run on the calling thread"),
+            }))
+        });
+    };
+    let slots: Vec<Mutex<Option<std::thread::Result<R>>>> =
+        jobs.iter().map(|_| Mutex::new(None)).collect();
+    let boxed = jobs
+        .into_iter()
+        .zip(&slots)
+        .map(|(j, slot)| {
+            Box::new(move || {
+                let r = as_worker(|| panic::catch_unwind(AssertUnwindSafe(j)));
+                *lock(slot) = Some(r);
+            }) as Job<'_>
+        })
+        .collect();
+    spawner.run(boxed);
+    let mut results = Vec::with_capacity(slots.len());
+    for slot in slots {
+        let r = slot.into_inner().unwrap_or_else(|e| e.into_inner());
+        results.push(r.unwrap_or_else(|| Err(Box::new("job not run by the spawner")))?);
+    }
+    Ok(results)
+}
+
+// Take a job out of its slot and run it.
+#[cfg(feature = "std")]
+fn run_slot<R>(slot: &Mutex<Option<impl FnOnce() -> R>>) -> R {
+    let job = lock(slot).take().expect("job run once");
+    job()
+}
+
+// Run `f` marked as a worker, restoring the previous mark since the thread
+// may be reused after the call.
+#[cfg(feature = "std")]
+fn as_worker<R>(f: impl FnOnce() -> R) -> R {
+    let in_worker = IN_WORKER.replace(true);
+    let r = f();
+    IN_WORKER.set(in_worker);
+    r
+}
+
+// Spawn worker `w` with the thread name, stack size and cores of `cfg`.
+#[cfg(feature = "std")]
+fn spawn<'scope, R: Send + 'scope>(
+    s: &'scope Scope<'scope, '_>,
+    cfg: Option<&ParConfig>,
+    w: usize,
+    f: impl FnOnce() -> R + Send + 'scope,
+) -> std::io::Result<ScopedJoinHandle<'scope, R>> {
+    let mut builder = std::thread::Builder::new();
+    if let Some(prefix) = cfg.and_then(|c| c.thread_name_prefix()) {
+        builder = builder.name(format!("{prefix}-{w}"));
+    }
+    if let Some(size) = cfg.and_then(|c| c.stack_size()) {
+        builder = builder.stack_size(size);
+    }
+    let cores = cfg.and_then(|c| c.worker_cores(w));
+    let priority = cfg.map_or(ThreadPriority::Normal, |c| c.priority());
+    builder.spawn_scoped(s, move || {
+        IN_WORKER.set(true);
+        if let Some(cores) = cores {
+            crate::affinity::pin_current_thread(&cores);
+        }
+        if priority != ThreadPriority::Normal {
+            crate::affinity::set_current_thread_priority(priority);
+        }
+        f()
+    })
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    // Set on the worker threads to detect nested calls.
+    static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+#[cfg(feature = "std")]
+type Queue<P> = Mutex<VecDeque<(usize, P)>>;
+
+// Lock a queue, a panicking job never holds the lock.
+#[cfg(feature = "std")]
+fn lock<Q>(q: &Mutex<Q>) -> MutexGuard<'_, Q> {
+    q.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Source of the parts pulled by the workers, numbered in pulling order.
+#[cfg(feature = "std")]
+trait Source<P>: Sync {
+    // Next part for worker `w` with its index.
+    fn next(&self, w: usize) -> Option<(usize, Part<P>)>;
+    // Number of parts; an estimate while parts remain to be created.
+    fn total(&self) -> usize;
+}
+
+// One shared queue, or one queue per worker holding a contiguous group.
+#[cfg(feature = "std")]
+struct Queues<P> {
+    queues: Vec<Queue<Part<P>>>,
+    scheduling: Scheduling,
+    total: usize,
+}
+
+#[cfg(feature = "std")]
+impl<P> Queues<P> {
+    fn new(scheduling: Scheduling, workers: usize, parts: Vec<Part<P>>) -> Self {
+        let total = parts.len();
+        // with no more parts than workers every part gets its own worker
+        let scheduling = if workers == total {
+            Scheduling::Static
+        } else {
+            scheduling
+        };
+        let mut parts = parts.into_iter().enumerate();
+        let queues = match scheduling {
+            Scheduling::Shared => vec![Mutex::new(parts.collect())],
+            Scheduling::Static | Scheduling::Dynamic => split::ranges(total, workers)
+                .into_iter()
+                .map(|r| Mutex::new(parts.by_ref().take(r.len()).collect()))
+                .collect(),
+            Scheduling::Cyclic => {
+                let mut groups: Vec<VecDeque<_>> = (0..workers).map(|_| VecDeque::new()).collect();
+                for (i, p) in parts {
+                    groups[i % workers].push_back((i, p));
+                }
+                groups.into_iter().map(Mutex::new).collect()
+            }
+        };
+        Queues {
+            queues,
+            scheduling,
+            total,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: Send> Source<P> for Queues<P> {
+    fn next(&self, w: usize) -> Option<(usize, Part<P>)> {
+        let queues = &self.queues;
+        let own = match self.scheduling {
+            Scheduling::Shared => return lock(&queues[0]).pop_front(),
+            Scheduling::Static | Scheduling::Dynamic | Scheduling::Cyclic => {
+                lock(&queues[w]).pop_front()
+            }
+        };
+        // the own queue is unlocked before stealing so that two thieves never
+        // wait on each other
+        if own.is_some() || self.scheduling != Scheduling::Dynamic {
+            return own;
+        }
+        let n = queues.len();
+        (1..n).find_map(|i| lock(&queues[(w + i) % n]).pop_back())
+    }
+    fn total(&self) -> usize {
+        self.total
+    }
+}
+
+// Number of sub-ranges per thread of the first guess of `Chunking::Adaptive`.
+#[cfg(feature = "std")]
+const ADAPTIVE_CHUNKS_PER_THREAD: usize = 16;
+
+// Target duration in seconds of an adaptive sub-range: long enough to
+// amortise the scheduling, short enough to balance the load.
+#[cfg(feature = "std")]
+const ADAPTIVE_CHUNK_TIME: f64 = 100e-6;
+
+// Sub-ranges split off the front of the data on demand, see
+// `Chunking::Adaptive`.
+#[cfg(feature = "std")]
+struct Adaptive<P> {
+    state: Mutex<AdaptiveState<P>>,
+    workers: usize,
+}
+
+#[cfg(feature = "std")]
+struct AdaptiveState<P> {
+    // elements not yet handed out, starting at `start`
+    data: Option<P>,
+    start: usize,
+    len: usize,
+    count: usize,
+    size: usize,
+    // smoothed throughput in elements per second
+    rate: Option<f64>,
+    // start time and length of the sub-range run by each worker
+    running: Vec<Option<(Instant, usize)>>,
+}
+
+#[cfg(feature = "std")]
+impl<P: Split> Adaptive<P> {
+    fn new(data: P, workers: usize, chunk_size: Option<usize>) -> Self {
+        let len = data.len();
+        let size = chunk_size.unwrap_or(len.div_ceil(workers.max(1) * ADAPTIVE_CHUNKS_PER_THREAD));
+        Adaptive {
+            state: Mutex::new(AdaptiveState {
+                data: Some(data),
+                start: 0,
+                len,
+                count: 0,
+                size: size.max(1),
+                rate: None,
+                running: vec![None; workers],
+            }),
+            workers,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<P: Split> Source<(Range<usize>, P)> for Adaptive<P> {
+    fn next(&self, w: usize) -> Option<(usize, Part<(Range<usize>, P)>)> {
+        let mut s = lock(&self.state);
+        // the previous sub-range of the worker has just completed
+        if let Some((t, n)) = s.running[w].take() {
+            let secs = t.elapsed().as_secs_f64();
+            if secs > 0.0 {
+                let rate = n as f64 / secs;
+                let rate = s.rate.map_or(rate, |r| (r + rate) / 2.0);
+                s.rate = Some(rate);
+                s.size = ((rate * ADAPTIVE_CHUNK_TIME) as usize).max(1);
+            }
+        }
+        let remaining = s.len - s.start;
+        if remaining == 0 {
+            return None;
+        }
+        let n = s.size.min(remaining.div_ceil(2 * self.workers));
+        let (head, tail) = s.data.take()?.split_at(n);
+        s.data = Some(tail);
+        let r = s.start..s.start + n;
+        let i = s.count;
+        s.start += n;
+        s.count += 1;
+        s.running[w] = Some((Instant::now(), n));
+        Some((i, (Some(r.clone()), (r, head))))
+    }
+    fn total(&self) -> usize {
+        let s = lock(&self.state);
+        s.count + (s.len - s.start).div_ceil(s.size)
+    }
+}
+
+//-----------------------------------------------------------------------------
+// Without `std` the panics cannot be caught and there is no thread to spawn:
+// the jobs handed to the spawner of the call pull the parts from a shared
+// counter, or all the parts run on the calling thread if no spawner is set.
+#[cfg(not(feature = "std"))]
+fn run_workers<P, R, S, I, F, D>(
+    call: &Call,
+    parts: Vec<Part<P>>,
+    init: I,
+    f: F,
+    fini: D,
+) -> Result<Vec<R>, PanicError>
+where
+    P: Send,
+    R: Send,
+    I: Fn(usize) -> S + Sync,
+    F: Fn(&mut S, P) -> R + Sync,
+    D: Fn(usize, S) + Sync,
+{
+    let num_parts = parts.len();
+    let slots: Vec<Slot<P, R>> = parts
+        .into_iter()
+        .map(|(_, p)| Slot(core::cell::UnsafeCell::new((Some(p), None))))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let completed = AtomicUsize::new(0);
+    let worker = |w: usize| {
+        let mut state = init(w);
+        loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            let Some(slot) = slots.get(i) else { break };
+            // SAFETY: each index is claimed by a single worker.
+            let (part, result) = unsafe { &mut *slot.0.get() };
+            if let Some(p) = part.take() {
+                *result = Some(f(&mut state, p));
+            }
+            let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(cfg) = call.cfg {
+                cfg.progress(done, num_parts);
+            }
+        }
+        fini(w, state);
+    };
+    let worker = &worker;
+    match call.cfg.and_then(|c| c.spawner()) {
+        Some(spawner) => {
+            let workers = crate::resolve_threads(call.num_threads).min(num_parts);
+            let jobs = (0..workers)
+                .map(|w| Box::new(move || worker(w)) as Job<'_>)
+                .collect();
+            spawner.run(jobs);
+        }
+        None => worker(0),
+    }
+    let results: Vec<R> = slots
+        .into_iter()
+        .filter_map(|s| s.0.into_inner().1)
+        .collect();
+    if results.len() == num_parts {
+        return Ok(results);
+    }
+    Err(PanicError {
+        panics: vec![ChunkPanic::new(
+            None,
+            None,
+            Box::new("job not run by the spawner"),
+        )],
+        completed: results.len(),
+        total: num_parts,
+    })
+}
+
+// Part and result of a part, accessed only by the worker which claimed it.
+#[cfg(not(feature = "std"))]
+struct Slot<P, R>(core::cell::UnsafeCell<(Option<P>, Option<R>)>);
+
+// SAFETY: the content of a slot is moved to and from a single worker.
+#[cfg(not(feature = "std"))]
+unsafe impl<P: Send, R: Send> Sync for Slot<P, R> {}
@@ -0,0 +1,258 @@
+//! Processing of memory-mapped files.
+
+use crate::split::{self, Split};
+use crate::{exec, InPlaceKernel, Kernel, ParError};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::Range;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::Arc;
+
+//-----------------------------------------------------------------------------
+mod sys {
+    use core::ffi::{c_int, c_long, c_void};
+
+    pub(super) const PROT_READ: c_int = 1;
+    pub(super) const PROT_WRITE: c_int = 2;
+    pub(super) const MAP_SHARED: c_int = 1;
+    pub(super) const SC_PAGESIZE: c_int = 30;
+
+    extern "C" {
+        pub(super) fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: c_long,
+        ) -> *mut c_void;
+        pub(super) fn munmap(addr: *mut c_void, len: usize) -> c_int;
+        pub(super) fn sysconf(name: c_int) -> c_long;
+    }
+}
+
+// Shared mapping of a whole file, unmapped on drop.
+struct Mmap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(file: &File, writable: bool) -> io::Result<Self> {
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large"))?;
+        if len == 0 {
+            // empty mappings are not allowed
+            return Ok(Mmap {
+                ptr: std::ptr::null_mut(),
+                len,
+            });
+        }
+        let prot = sys::PROT_READ | if writable { sys::PROT_WRITE } else { 0 };
+        // SAFETY: a new mapping is requested at an address chosen by the
+        // kernel, `fd` is a valid open file.
+        let ptr = unsafe {
+            sys::mmap(
+                std::ptr::null_mut(),
+                len,
+                prot,
+                sys::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        // `MAP_FAILED`
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mmap {
+            ptr: ptr.cast(),
+            len,
+        })
+    }
+
+    // View the mapping as elements of type `T`, ignoring trailing bytes.
+    //
+    // SAFETY: the file must not be modified by anyone else while the slice
+    // is alive.
+    unsafe fn slice<T: Plain>(&self) -> &[T] {
+        if self.len == 0 {
+            return &[];
+        }
+        // pages are aligned for every `Plain` type
+        std::slice::from_raw_parts(self.ptr.cast(), self.len / size_of::<T>())
+    }
+
+    // Same as `slice` for a writable mapping.
+    unsafe fn slice_mut<T: Plain>(&mut self) -> &mut [T] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        std::slice::from_raw_parts_mut(self.ptr.cast(), self.len / size_of::<T>())
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: `ptr` and `len` describe a mapping created by `new`.
+            unsafe { sys::munmap(self.ptr.cast(), self.len) };
+        }
+    }
+}
+
+// Sub-ranges starting at page boundaries.
+fn page_chunks<P: Split>(data: P, num_threads: usize) -> Vec<(Range<usize>, P)> {
+    // SAFETY: no preconditions.
+    let page = usize::try_from(unsafe { sys::sysconf(sys::SC_PAGESIZE) }).unwrap_or(4096);
+    let elem = P::elem_size().max(1);
+    let ranges = split::ranges(data.len(), num_threads);
+    let ranges = split::align_ranges(ranges, page / crate::config::gcd(page, elem), 0);
+    split::zip_ranges(data, ranges)
+}
+
+//-----------------------------------------------------------------------------
+/// Element types valid for any bit pattern, which can be read from and
+/// written to a file as raw bytes.
+///
+/// # Safety
+///
+/// Every bit pattern of `size_of::<Self>()` bytes must be a valid value, and
+/// the alignment must not exceed the page size.
+pub unsafe trait Plain: Copy + Send + Sync + 'static {}
+
+macro_rules! impl_plain {
+    ($($t:ty),+) => {
+        // SAFETY: primitive numeric types accept every bit pattern.
+        $(unsafe impl Plain for $t {})+
+    };
+}
+impl_plain!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+// SAFETY: arrays of `Plain` elements accept every bit pattern.
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {}
+
+//-----------------------------------------------------------------------------
+/// Map the file at `input` as a sequence of `S` and the file at `output`,
+/// created or truncated to hold as many `D`, then run
+/// [`par_map_to`](crate::par_map_to) with the sub-ranges starting at page
+/// boundaries.
+///
+/// The files are processed through the page cache: only the pages being
+/// accessed need to be in memory, datasets larger than the RAM can be
+/// transformed. Trailing bytes of `input` not forming a whole `S` are
+/// ignored. Failures to open or map the files return [`ParError::Io`], and
+/// [`ParError::Overlap`] is returned if `input` and `output` are the same file,
+/// also through a link.
+///
+/// # Safety
+///
+/// Neither file may be modified by other threads or processes during the
+/// call.
+pub unsafe fn par_map_file<S: Plain, D: Plain, K: Kernel<S, D> + ?Sized>(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    num_threads: usize,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    let src_file = File::open(input)?;
+    // the output is truncated below: the same file would be read while written
+    if let Ok(out) = std::fs::metadata(output.as_ref()) {
+        let inp = src_file.metadata()?;
+        if (inp.dev(), inp.ino()) == (out.dev(), out.ino()) {
+            return Err(ParError::Overlap);
+        }
+    }
+    let src = Mmap::new(&src_file, false)?;
+    let len = src.len / size_of::<S>();
+    let dest_file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(output)?;
+    dest_file.set_len((len * size_of::<D>()) as u64)?;
+    let mut dest = Mmap::new(&dest_file, true)?;
+    // SAFETY: the files are distinct and the caller guarantees that they are
+    // not modified.
+    let (s, d) = unsafe { (src.slice::<S>(), dest.slice_mut::<D>()) };
+    exec::run_chunks(
+        num_threads,
+        page_chunks((s, d), num_threads),
+        |(_, (s, d))| fr.run(s, d),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Map the file at `path` as a sequence of `T` and run
+/// [`par_in_place_map`](crate::par_in_place_map) on it, see
+/// [`par_map_file`].
+///
+/// # Safety
+///
+/// The file must not be modified by other threads or processes during the
+/// call.
+pub unsafe fn par_in_place_map_file<T: Plain, K: InPlaceKernel<T> + ?Sized>(
+    path: impl AsRef<Path>,
+    num_threads: usize,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut map = Mmap::new(&file, true)?;
+    // SAFETY: the caller guarantees that the file is not modified.
+    let dest = unsafe { map.slice_mut::<T>() };
+    exec::run_chunks(num_threads, page_chunks(dest, num_threads), |(_, d)| {
+        fr.run(d)
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_map_file_test() -> Result<(), ParError> {
+        let dir = std::env::temp_dir().join(format!("par_seq_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let (input, output) = (dir.join("in.bin"), dir.join("out.bin"));
+        let src: Vec<u8> = (0..100_000_u32).flat_map(|i| i.to_le_bytes()).collect();
+        std::fs::write(&input, [&src[..], &[1, 2]].concat())?;
+        let kernel_fun = |s: &[u32], d: &mut [u64]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = u64::from(u32::from_le(*s)) * 3;
+            }
+        };
+        // SAFETY: the files are private to the test.
+        unsafe { par_map_file(&input, &output, 4, kernel!(kernel_fun))? };
+        let out = std::fs::read(&output)?;
+        assert_eq!(out.len(), 800_000);
+        let expected: Vec<u8> = (0..100_000_u64)
+            .flat_map(|i| (i * 3).to_ne_bytes())
+            .collect();
+        assert_eq!(out, expected);
+        let kernel_fun = |d: &mut [u8]| d.iter_mut().for_each(|e| *e = e.wrapping_add(1));
+        // SAFETY: the file is private to the test.
+        unsafe { par_in_place_map_file(&input, 3, kernel!(kernel_fun))? };
+        let data = std::fs::read(&input)?;
+        assert!(data[..src.len()]
+            .iter()
+            .zip(&src)
+            .all(|(d, s)| *d == s.wrapping_add(1)));
+        std::fs::write(&input, [])?;
+        let kernel_fun = |s: &[u8], d: &mut [u8]| d.copy_from_slice(s);
+        // SAFETY: the files are private to the test.
+        unsafe { par_map_file(&input, &output, 4, kernel!(kernel_fun))? };
+        assert!(std::fs::read(&output)?.is_empty());
+        // SAFETY: the file is private to the test.
+        let r = unsafe { par_map_file(&output, &output, 4, kernel!(kernel_fun)) };
+        assert!(matches!(r, Err(ParError::Overlap)));
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+//! Filtering and compaction.
+
+use crate::{exec, split, ParError, PredFun};
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::Arc;
+
+//-----------------------------------------------------------------------------
+/// Return the elements matching `pred`, in their original order.
+///
+/// Each thread evaluates the predicate on its sub-range and counts the
+/// matches; the counts give each sub-range its offset in the output, which
+/// is then filled in parallel.
+pub fn par_filter<T: Clone + Send + Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<Vec<T>, ParError> {
+    let chunks = split::chunks(src, num_threads);
+    let flags = exec::run_chunks(num_threads, chunks, |(_, s)| {
+        let f: Vec<bool> = s.iter().map(|e| pred(e)).collect();
+        let count = f.iter().filter(|m| **m).count();
+        (f, count)
+    })?;
+    let mut start = 0;
+    let out_ranges: Vec<_> = flags
+        .iter()
+        .map(|(_, count)| {
+            start += count;
+            start - count..start
+        })
+        .collect();
+    let mut dest = Vec::with_capacity(start);
+    let parts = split::split_ranges(&mut dest.spare_capacity_mut()[..start], &out_ranges);
+    let chunks: Vec<_> = split::chunks(src, num_threads)
+        .into_iter()
+        .zip(flags)
+        .zip(parts)
+        .collect();
+    exec::run(num_threads, chunks, |(((_, s), (f, _)), d)| {
+        let matches = s.iter().zip(f).filter(|(_, m)| *m).map(|(e, _)| e);
+        for (d, e) in d.iter_mut().zip(matches) {
+            d.write(e.clone());
+        }
+    })?;
+    // SAFETY: each of the `start` elements was written by the sub-range
+    // owning its output range, all the threads completed successfully.
+    unsafe { dest.set_len(start) };
+    Ok(dest)
+}
+
+//-----------------------------------------------------------------------------
+/// Return the indices of the elements matching `pred`, in increasing order.
+///
+/// Each thread collects the indices of the matches in its sub-range; the
+/// numbers of matches give each sub-range its offset in the output, into
+/// which the local lists are then copied in parallel.
+pub fn par_positions<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<Vec<usize>, ParError> {
+    let local = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(r, s)| {
+        let matches = s.iter().enumerate().filter(|(_, e)| pred(e));
+        matches.map(|(i, _)| r.start + i).collect::<Vec<_>>()
+    })?;
+    let mut start = 0;
+    let out_ranges: Vec<_> = local
+        .iter()
+        .map(|l| {
+            start += l.len();
+            start - l.len()..start
+        })
+        .collect();
+    let mut dest = vec![0; start];
+    let parts = split::split_ranges(&mut dest[..], &out_ranges);
+    let jobs: Vec<_> = local.into_iter().zip(parts).collect();
+    exec::run(num_threads, jobs, |(l, d)| d.copy_from_slice(&l))?;
+    Ok(dest)
+}
+
+//-----------------------------------------------------------------------------
+/// Keep only the elements of `v` matching `pred`, in their original order,
+/// and return the new length.
+///
+/// Each thread evaluates the predicate on its sub-range, then drops the
+/// rejected elements and moves the kept ones to the front of the sub-range;
+/// the compacted sub-ranges are finally moved to their offsets, given by the
+/// numbers of kept elements, with one memory copy each.
+///
+/// ```rust,ignore
+/// let len = par_retain(&mut records, 8, kernel!(|r: &Record| !r.deleted))?;
+/// ```
+///
+/// `v` is left unchanged if `pred` panics; if a destructor panics, `v` is
+/// left empty and its remaining elements are leaked.
+pub fn par_retain<T: Send + Sync>(
+    v: &mut Vec<T>,
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<usize, ParError> {
+    let chunks = split::chunks(&v[..], num_threads);
+    let ranges: Vec<_> = chunks.iter().map(|(r, _)| r.clone()).collect();
+    let flags = exec::run_chunks(num_threads, chunks, |(_, s)| {
+        s.iter().map(|e| pred(e)).collect::<Vec<bool>>()
+    })?;
+    let len = v.len();
+    // SAFETY: from here on the elements are handled as uninitialized memory,
+    // `v` owns none of them until its length is restored.
+    unsafe { v.set_len(0) };
+    let data = unsafe { &mut *(v.spare_capacity_mut() as *mut [MaybeUninit<T>]) };
+    let data = &mut data[..len];
+    let parts = split::split_ranges(&mut *data, &ranges);
+    let jobs: Vec<_> = parts.into_iter().zip(flags).collect();
+    let counts = exec::run(num_threads, jobs, |(d, f)| {
+        let p = d.as_mut_ptr();
+        let mut kept = 0;
+        for (i, keep) in f.into_iter().enumerate() {
+            // SAFETY: `i` and `kept <= i` are in the sub-range, elements
+            // before `kept` are kept ones, elements from `i` are untouched.
+            unsafe {
+                if !keep {
+                    ptr::drop_in_place((*p.add(i)).as_mut_ptr());
+                } else {
+                    if kept != i {
+                        ptr::copy_nonoverlapping(p.add(i), p.add(kept), 1);
+                    }
+                    kept += 1;
+                }
+            }
+        }
+        kept
+    })?;
+    let base = data.as_mut_ptr();
+    let mut start = 0;
+    for (r, count) in ranges.iter().zip(counts) {
+        // SAFETY: the kept elements of the sub-range are at its front, and
+        // `start <= r.start`: moving the sub-ranges in order overwrites only
+        // elements already moved or dropped.
+        unsafe { ptr::copy(base.add(r.start), base.add(start), count) };
+        start += count;
+    }
+    // SAFETY: the first `start` elements are the kept ones.
+    unsafe { v.set_len(start) };
+    Ok(start)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_filter_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1000).collect();
+        let even = par_filter(&src, 4, kernel!(|e: &u32| e.is_multiple_of(2)))?;
+        assert_eq!(even, (0..1000).step_by(2).collect::<Vec<_>>());
+        let strings: Vec<String> = src.iter().map(|e| e.to_string()).collect();
+        let sevens = par_filter(&strings, 3, kernel!(|s: &String| s.contains('7')))?;
+        assert_eq!(sevens.len(), 271);
+        assert_eq!(sevens[0], "7");
+        assert!(par_filter(&src[..0], 4, kernel!(|_: &u32| true))?.is_empty());
+        Ok(())
+    }
+    #[test]
+    fn par_retain_test() -> Result<(), ParError> {
+        let src: Vec<String> = (0..10_000).map(|i| i.to_string()).collect();
+        for n in [1, 4, 7] {
+            let mut v = src.clone();
+            let len = par_retain(&mut v, n, kernel!(|s: &String| s.ends_with('7')))?;
+            assert_eq!(len, 1000);
+            assert!(v.iter().eq(src.iter().filter(|s| s.ends_with('7'))));
+        }
+        let mut v = src.clone();
+        assert_eq!(par_retain(&mut v, 4, kernel!(|_: &String| true))?, 10_000);
+        assert_eq!(v, src);
+        assert_eq!(par_retain(&mut v, 4, kernel!(|_: &String| false))?, 0);
+        assert!(v.is_empty());
+        // the predicate panics: unchanged
+        let mut v = src.clone();
+        let r = par_retain(&mut v, 4, kernel!(|s: &String| s != "5000"));
+        assert!(r.is_ok());
+        let r = par_retain(&mut v, 4, kernel!(|s: &String| s.len() < 4 || panic!()));
+        assert!(matches!(r, Err(ParError::Panic(_))));
+        assert_eq!(v.len(), 9999);
+        Ok(())
+    }
+    #[test]
+    fn par_positions_test() -> Result<(), ParError> {
+        let flags: Vec<bool> = (0..10_000).map(|i| i % 97 == 3).collect();
+        let expected: Vec<usize> = (0..10_000).filter(|i| i % 97 == 3).collect();
+        for n in [1, 4, 7] {
+            assert_eq!(par_positions(&flags, n, kernel!(|f: &bool| *f))?, expected);
+        }
+        assert!(par_positions(&flags, 4, kernel!(|_: &bool| false))?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Futures and handles resolving when a parallel call completes.
+
+use crate::{InPlaceKernel, Kernel, ParError};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::JoinHandle;
+
+//-----------------------------------------------------------------------------
+struct Slot<R> {
+    result: Option<R>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by the `*_async` functions, resolves to the result of the
+/// parallel call once all its threads have completed.
+///
+/// The call runs on a separate coordinating thread, polling never blocks.
+pub struct ParFuture<R> {
+    slot: Arc<Mutex<Slot<R>>>,
+}
+
+impl<R> Future for ParFuture<R> {
+    type Output = R;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let mut slot = self.slot.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.result.take() {
+            Some(r) => Poll::Ready(r),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// Run `f` on a new thread, resolving the returned future with its result.
+fn spawn<R: Send + 'static>(f: impl FnOnce() -> R + Send + 'static) -> ParFuture<R> {
+    let slot = Arc::new(Mutex::new(Slot {
+        result: None,
+        waker: None,
+    }));
+    let s = slot.clone();
+    std::thread::spawn(move || {
+        let r = f();
+        let mut slot = s.lock().unwrap_or_else(|e| e.into_inner());
+        slot.result = Some(r);
+        if let Some(w) = slot.waker.take() {
+            w.wake();
+        }
+    });
+    ParFuture { slot }
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`](crate::par_map) without blocking the caller: the
+/// returned future resolves to `dest` once filled.
+///
+/// The sequences and the kernel are moved into the call, since the work
+/// outlives the calling scope.
+pub fn par_map_async<T: Send + Sync + 'static, K: Kernel<T> + ?Sized + 'static>(
+    src: Arc<[T]>,
+    mut dest: Vec<T>,
+    num_threads: usize,
+    fr: Arc<K>,
+) -> ParFuture<Result<Vec<T>, ParError>> {
+    spawn(move || {
+        crate::par_map(&src, &mut dest, num_threads, fr)?;
+        Ok(dest)
+    })
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`](crate::par_in_place_map) without blocking the
+/// caller, see [`par_map_async`].
+pub fn par_in_place_map_async<T: Send + 'static, K: InPlaceKernel<T> + ?Sized + 'static>(
+    mut dest: Vec<T>,
+    num_threads: usize,
+    fr: Arc<K>,
+) -> ParFuture<Result<Vec<T>, ParError>> {
+    spawn(move || {
+        crate::par_in_place_map(&mut dest, num_threads, fr)?;
+        Ok(dest)
+    })
+}
+
+//-----------------------------------------------------------------------------
+/// Handle returned by the `*_spawn` functions, to wait for the result of a
+/// parallel call running in the background without an async executor.
+pub struct ParHandle<R> {
+    thread: JoinHandle<R>,
+}
+
+impl<R> ParHandle<R> {
+    /// Block until the call completes and return its result.
+    pub fn join(self) -> R {
+        // kernel panics are caught by the call itself
+        self.thread
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    }
+    /// Return `true` if the call has completed, in which case
+    /// [`join`](ParHandle::join) does not block.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`](crate::par_map) returning immediately: the call runs
+/// on a separate coordinating thread while the caller is free to do other
+/// work, e.g. I/O, and [`ParHandle::join`] returns `dest` once filled.
+///
+/// ```rust,ignore
+/// let handle = par_map_spawn(src, dest, 8, kernel!(transform));
+/// let next = read_next_frame()?;
+/// let dest = handle.join()?;
+/// ```
+pub fn par_map_spawn<T: Send + Sync + 'static, K: Kernel<T> + ?Sized + 'static>(
+    src: Arc<[T]>,
+    mut dest: Vec<T>,
+    num_threads: usize,
+    fr: Arc<K>,
+) -> ParHandle<Result<Vec<T>, ParError>> {
+    let thread = std::thread::spawn(move || {
+        crate::par_map(&src, &mut dest, num_threads, fr)?;
+        Ok(dest)
+    });
+    ParHandle { thread }
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`](crate::par_in_place_map) returning
+/// immediately, see [`par_map_spawn`].
+pub fn par_in_place_map_spawn<T: Send + 'static, K: InPlaceKernel<T> + ?Sized + 'static>(
+    mut dest: Vec<T>,
+    num_threads: usize,
+    fr: Arc<K>,
+) -> ParHandle<Result<Vec<T>, ParError>> {
+    let thread = std::thread::spawn(move || {
+        crate::par_in_place_map(&mut dest, num_threads, fr)?;
+        Ok(dest)
+    });
+    ParHandle { thread }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    use std::task::Wake;
+    // minimal executor parking the thread until woken
+    struct Unpark(std::thread::Thread);
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = std::pin::pin!(f);
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(r) = f.as_mut().poll(&mut cx) {
+                return r;
+            }
+            std::thread::park();
+        }
+    }
+    #[test]
+    fn par_map_async_test() -> Result<(), ParError> {
+        let src: Arc<[u32]> = (0..100).collect();
+        let kernel_fun = |s: &[u32], d: &mut [u32]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = s + 1;
+            }
+        };
+        let dest = block_on(par_map_async(src, vec![0; 100], 4, kernel!(kernel_fun)))?;
+        assert_eq!(dest, (1..101).collect::<Vec<_>>());
+        let dest = block_on(par_in_place_map_async(
+            dest,
+            3,
+            kernel!(|d: &mut [u32]| d.fill(0)),
+        ))?;
+        assert!(dest.iter().all(|e| *e == 0));
+        Ok(())
+    }
+    #[test]
+    fn par_map_spawn_test() -> Result<(), ParError> {
+        let src: Arc<[u32]> = (0..1000).collect();
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let rx = Mutex::new(rx);
+        // the kernel waits for the caller, which must not be blocked
+        let kernel_fun = move |s: &[u32], d: &mut [u32]| {
+            let _ = rx.lock().unwrap().recv();
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = s * 2;
+            }
+        };
+        let handle = par_map_spawn(src, vec![0; 1000], 4, kernel!(kernel_fun));
+        assert!(!handle.is_finished());
+        drop(tx);
+        let dest = handle.join()?;
+        assert!(dest.iter().enumerate().all(|(i, e)| *e == i as u32 * 2));
+        let handle = par_in_place_map_spawn(dest, 3, kernel!(|d: &mut [u32]| d.fill(1)));
+        assert!(handle.join()?.iter().all(|e| *e == 1));
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+//! Indexed gather and scatter.
+
+use crate::{exec, split, CombineFun, IndexOutOfBounds, ParError, ScatterError};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+//-----------------------------------------------------------------------------
+/// Set `dest[i]` to a clone of `src[indices[i]]`, splitting the index array
+/// among the threads.
+///
+/// An index past the end of `src` fails its sub-range with an
+/// [`IndexOutOfBounds`] error reported in [`ParError::Kernel`]; the other
+/// sub-ranges are still gathered. `indices` and `dest` must have the same
+/// length, otherwise [`ParError::LengthMismatch`] is returned before
+/// gathering anything.
+pub fn par_gather<T: Clone + Send + Sync>(
+    src: &[T],
+    indices: &[usize],
+    dest: &mut [T],
+    num_threads: usize,
+) -> Result<(), ParError<IndexOutOfBounds>> {
+    crate::check_len(indices.len(), dest.len())?;
+    let chunks = split::chunks((indices, dest), num_threads);
+    let results = exec::run_chunks(num_threads, chunks, |(r, (idx, d))| {
+        (r.clone(), gather(src, r.start, idx, d))
+    })?;
+    crate::chunk_errors(results)
+}
+
+// Gather the sub-range of `dest` starting at position `start`.
+fn gather<T: Clone>(
+    src: &[T],
+    start: usize,
+    indices: &[usize],
+    dest: &mut [T],
+) -> Result<(), IndexOutOfBounds> {
+    for (p, (d, &index)) in dest.iter_mut().zip(indices).enumerate() {
+        let e = src.get(index).ok_or(IndexOutOfBounds {
+            position: start + p,
+            index,
+            len: src.len(),
+        })?;
+        d.clone_from(e);
+    }
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Handling of the destination elements targeted by more than one index in
+/// [`par_scatter`].
+pub enum Conflict<'a, T> {
+    /// The element with the last position in the index array is written, as
+    /// in a serial scatter.
+    Overwrite,
+    /// A destination element written more than once fails its sub-range
+    /// with [`ScatterError::Conflict`].
+    Error,
+    /// Each element is combined with the destination element, in index
+    /// array order: `kernel!(|d, s| d + s)` accumulates into `dest`.
+    Combine(Arc<CombineFun<'a, T>>),
+}
+
+/// Set `dest[indices[i]]` to a clone of `src[i]`, resolving the elements
+/// targeted by more than one index according to `conflict`.
+///
+/// Each thread first sorts the positions of its part of the index array by
+/// destination sub-range; each thread then writes one sub-range of `dest`,
+/// so that no destination element is written concurrently and no atomic
+/// operation is needed. Errors are reported by destination sub-range,
+/// except for indices out of bounds which are reported by sub-range of the
+/// index array before anything is written. `src` and `indices` must have the
+/// same length, otherwise [`ParError::LengthMismatch`] is returned before
+/// scattering anything.
+pub fn par_scatter<T: Clone + Send + Sync>(
+    src: &[T],
+    indices: &[usize],
+    dest: &mut [T],
+    num_threads: usize,
+    conflict: Conflict<T>,
+) -> Result<(), ParError<ScatterError>> {
+    crate::check_len(src.len(), indices.len())?;
+    let len = dest.len();
+    let bands = split::ranges(len, num_threads);
+    let ends: Vec<usize> = bands.iter().map(|r| r.end).collect();
+    let chunks = split::chunks((src, indices), num_threads);
+    let results = exec::run_chunks(num_threads, chunks, |(r, (_, idx))| {
+        let mut positions = vec![Vec::new(); ends.len()];
+        for (p, &index) in idx.iter().enumerate() {
+            if index >= len {
+                let e = IndexOutOfBounds {
+                    position: r.start + p,
+                    index,
+                    len,
+                };
+                return (r.clone(), Err(ScatterError::OutOfBounds(e)));
+            }
+            positions[ends.partition_point(|e| *e <= index)].push(r.start + p);
+        }
+        (r.clone(), Ok(positions))
+    })?;
+    if results.iter().any(|(_, r)| r.is_err()) {
+        return crate::chunk_errors(
+            results
+                .into_iter()
+                .map(|(r, p)| (r, p.map(|_| ())))
+                .collect(),
+        );
+    }
+    let positions: Vec<_> = results.into_iter().filter_map(|(_, p)| p.ok()).collect();
+    let chunks = split::zip_ranges(dest, bands);
+    let conflict = &conflict;
+    let results = exec::run(
+        num_threads,
+        chunks.into_iter().enumerate().collect(),
+        |(b, (r, d))| {
+            let written = positions.iter().flat_map(|p| &p[b]);
+            (
+                r.clone(),
+                scatter(src, indices, r.start, d, written, conflict),
+            )
+        },
+    )?;
+    crate::chunk_errors(results)
+}
+
+// Write the elements at `positions`, in increasing order, into the sub-range
+// of `dest` starting at index `start`.
+fn scatter<'p, T: Clone>(
+    src: &[T],
+    indices: &[usize],
+    start: usize,
+    dest: &mut [T],
+    positions: impl Iterator<Item = &'p usize>,
+    conflict: &Conflict<T>,
+) -> Result<(), ScatterError> {
+    let mut first = match conflict {
+        Conflict::Error => vec![None; dest.len()],
+        _ => Vec::new(),
+    };
+    for &p in positions {
+        let (index, s) = (indices[p], &src[p]);
+        let d = &mut dest[index - start];
+        match conflict {
+            Conflict::Overwrite => d.clone_from(s),
+            Conflict::Error => match first[index - start].replace(p) {
+                None => d.clone_from(s),
+                Some(q) => {
+                    return Err(ScatterError::Conflict {
+                        index,
+                        positions: (q, p),
+                    })
+                }
+            },
+            Conflict::Combine(f) => *d = f(d.clone(), s.clone()),
+        }
+    }
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn par_gather_test() {
+        let src: Vec<u32> = (0..100).map(|i| i * 10).collect();
+        let indices: Vec<usize> = (0..100).rev().collect();
+        let mut dest = vec![0_u32; 100];
+        assert!(par_gather(&src, &indices, &mut dest, 4).is_ok());
+        assert_eq!(dest, src.iter().rev().copied().collect::<Vec<_>>());
+        let indices = [3, 1, 200, 0];
+        match par_gather(&src, &indices, &mut dest[..4], 2) {
+            Err(ParError::Kernel(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].index, 1);
+                assert_eq!(
+                    errors[0].error,
+                    IndexOutOfBounds {
+                        position: 2,
+                        index: 200,
+                        len: 100
+                    }
+                );
+            }
+            r => panic!("unexpected {r:?}"),
+        }
+        assert_eq!(dest[..2], [30, 10]);
+        let r = par_gather(&src, &[1, 2, 3], &mut dest, 2);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 3, dest: 100 })
+        ));
+        assert_eq!(dest[..2], [30, 10]);
+    }
+    #[test]
+    fn par_scatter_test() -> Result<(), ParError<ScatterError>> {
+        let src: Vec<u32> = (0..100).collect();
+        let indices: Vec<usize> = (0..100).map(|i| (i * 7) % 100).collect();
+        let mut dest = vec![0_u32; 100];
+        par_scatter(&src, &indices, &mut dest, 4, Conflict::Error)?;
+        assert!(indices.iter().zip(&src).all(|(i, s)| dest[*i] == *s));
+        // every element hits index i % 10
+        let indices: Vec<usize> = (0..100).map(|i| i % 10).collect();
+        let mut dest = vec![0_u32; 10];
+        par_scatter(&src, &indices, &mut dest, 3, Conflict::Overwrite)?;
+        assert_eq!(dest, (90..100).collect::<Vec<_>>());
+        let mut dest = vec![1_u32; 10];
+        par_scatter(
+            &src,
+            &indices,
+            &mut dest,
+            3,
+            Conflict::Combine(crate::kernel!(|d, s| d + s)),
+        )?;
+        assert_eq!(dest, (0..10).map(|i| 1 + 10 * i + 450).collect::<Vec<_>>());
+        match par_scatter(&src, &indices, &mut dest, 3, Conflict::Error) {
+            Err(ParError::Kernel(errors)) => assert_eq!(
+                errors[0].error,
+                ScatterError::Conflict {
+                    index: 0,
+                    positions: (0, 10)
+                }
+            ),
+            r => panic!("unexpected {r:?}"),
+        }
+        let r = par_scatter(&src[..3], &[0, 10, 1], &mut dest, 3, Conflict::Overwrite);
+        assert!(matches!(r, Err(ParError::Kernel(e)) if e[0].index == 1));
+        let before = dest.clone();
+        for (s, i) in [(&src[..3], &indices[..2]), (&src[..2], &indices[..3])] {
+            let r = par_scatter(s, i, &mut dest, 3, Conflict::Overwrite);
+            assert!(matches!(r, Err(ParError::LengthMismatch { .. })));
+        }
+        assert_eq!(dest, before);
+        Ok(())
+    }
+}
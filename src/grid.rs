@@ -0,0 +1,352 @@
+//! Two-dimensional data stored in flat buffers.
+
+use crate::{exec, split, ParError};
+use std::ops::Range;
+use std::sync::Arc;
+
+type Map2dKernelFun<'a, T> = dyn Fn((usize, usize), &[T], &mut [T], usize) + Send + Sync + 'a;
+type RowsKernelFun<'a, T> = dyn Fn(Range<usize>, Rows<'_, T>) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Layout of a `rows × cols` grid in a flat buffer, rows are `stride`
+/// elements apart (`stride >= cols`, extra elements are row padding).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Grid {
+    /// Number of rows.
+    pub rows: usize,
+    /// Number of elements per row.
+    pub cols: usize,
+    /// Distance in elements between the start of consecutive rows.
+    pub stride: usize,
+}
+
+impl Grid {
+    /// Grid with no row padding.
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            stride: cols,
+        }
+    }
+    /// Same grid with rows `stride` elements apart.
+    pub fn with_stride(self, stride: usize) -> Self {
+        assert!(
+            stride >= self.cols,
+            "stride smaller than the number of columns"
+        );
+        Self { stride, ..self }
+    }
+    /// Minimum length of a buffer holding the grid.
+    pub fn min_len(&self) -> usize {
+        match self.rows {
+            0 => 0,
+            r => (r - 1) * self.stride + self.cols,
+        }
+    }
+    // Element ranges of consecutive bands of rows; full strides except for
+    // the last band, which ends with the buffer.
+    pub(crate) fn bands(&self, band_rows: usize, num_threads: usize) -> Vec<Range<usize>> {
+        let rows = if band_rows == 0 {
+            split::ranges(self.rows, num_threads)
+        } else {
+            split::fixed_ranges(self.rows, band_rows)
+        };
+        rows.into_iter()
+            .map(|r| r.start * self.stride..(r.end * self.stride).min(self.min_len()))
+            .collect()
+    }
+    // `min_len`, `None` if it overflows.
+    fn checked_len(&self) -> Option<usize> {
+        match self.rows {
+            0 => Some(0),
+            r => (r - 1).checked_mul(self.stride)?.checked_add(self.cols),
+        }
+    }
+}
+
+// Number of elements `size` if buffers of lengths `lens` can all hold them,
+// otherwise `ParError::LengthMismatch` with `size` as `src` (`usize::MAX` if
+// it overflowed) and the first length too small as `dest`.
+fn check_fits(size: Option<usize>, lens: &[usize]) -> Result<usize, ParError> {
+    let src = size.unwrap_or(usize::MAX);
+    match lens.iter().find(|&&len| size.is_none_or(|s| len < s)) {
+        Some(&dest) => Err(ParError::LengthMismatch { src, dest }),
+        None => Ok(src),
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Map `src` into `dest`, both laid out as `grid`, splitting by bands of
+/// `band_rows` rows (`0` for one band per thread).
+///
+/// The kernel receives `Fn((row, col), src_band, dest_band, stride)` where
+/// `(row, col)` is the origin of the band in the grid, and the bands start at
+/// the first element of the origin row. Bands always span whole rows:
+/// sub-dividing rows across threads would make the writable slices overlap.
+///
+/// Returns [`ParError::LengthMismatch`] if a buffer is smaller than
+/// [`Grid::min_len`], with the length of the buffer as `dest` and the minimum
+/// length as `src`.
+pub fn par_map_2d<T: Send + Sync>(
+    src: &[T],
+    dest: &mut [T],
+    grid: Grid,
+    band_rows: usize,
+    num_threads: usize,
+    fr: Arc<Map2dKernelFun<T>>,
+) -> Result<(), ParError> {
+    let len = check_fits(grid.checked_len(), &[src.len(), dest.len()])?;
+    let bands = grid.bands(band_rows, num_threads);
+    let parts = split::split_ranges((&src[..len], &mut dest[..len]), &bands);
+    let chunks = bands.into_iter().zip(parts).collect();
+    exec::run_chunks(num_threads, chunks, |(r, (s, d))| {
+        fr((r.start / grid.stride, 0), s, d, grid.stride)
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Mutable view of consecutive rows of an image, excluding the row padding.
+pub struct Rows<'a, T> {
+    data: &'a mut [T],
+    width: usize,
+    stride: usize,
+}
+
+impl<'a, T> Rows<'a, T> {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.data.len().div_ceil(self.stride)
+    }
+    /// Return `true` if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    /// The `width` pixels of row `i` of the view.
+    pub fn row_mut(&mut self, i: usize) -> &mut [T] {
+        let start = i * self.stride;
+        &mut self.data[start..start + self.width]
+    }
+    /// Iterate over the rows of the view.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut [T]> {
+        let width = self.width;
+        self.data
+            .chunks_mut(self.stride)
+            .map(move |r| &mut r[..width])
+    }
+    /// The rows including the padding between them, rows are
+    /// [`stride`](Rows::stride) elements apart.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.data
+    }
+    /// Distance in pixels between the start of consecutive rows.
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Modify in-place the `width × height` image of pixels `T` in `buf`, whose
+/// rows start every `stride_bytes` bytes, by bands of rows.
+///
+/// The kernel receives `Fn(y_range, rows)`: the rows of the band and their
+/// range in the image; padding after the rows is never handed to the kernel
+/// through [`Rows::row_mut`] or [`Rows::iter_mut`].
+///
+/// ```rust,ignore
+/// par_rows(&mut rgba, width, height, stride, 8, kernel!(|ys: Range<usize>, mut rows: Rows<[u8; 4]>| {
+///     for (y, row) in ys.zip(rows.iter_mut()) {
+///         row.iter_mut().for_each(|p| p[3] = alpha(y));
+///     }
+/// }))?;
+/// ```
+///
+/// Panics if `stride_bytes` is not a multiple of the size of `T`, if a row
+/// does not fit in the stride or if `buf` is too small for the image.
+pub fn par_rows<T: Send>(
+    buf: &mut [T],
+    width: usize,
+    height: usize,
+    stride_bytes: usize,
+    num_threads: usize,
+    fr: Arc<RowsKernelFun<T>>,
+) -> Result<(), ParError> {
+    let size = std::mem::size_of::<T>().max(1);
+    assert!(
+        stride_bytes.is_multiple_of(size),
+        "stride not a multiple of the pixel size"
+    );
+    let grid = Grid::new(height, width).with_stride(stride_bytes / size);
+    let len = grid.min_len();
+    assert!(buf.len() >= len, "buffer smaller than image");
+    if width == 0 {
+        return Ok(());
+    }
+    let bands = grid.bands(0, num_threads);
+    let parts = split::split_ranges(&mut buf[..len], &bands);
+    let chunks = bands.into_iter().zip(parts).collect();
+    exec::run_chunks(num_threads, chunks, |(r, data)| {
+        let y = r.start / grid.stride;
+        let rows = Rows {
+            data,
+            width,
+            stride: grid.stride,
+        };
+        fr(y..y + rows.len(), rows)
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+// Side of the square tiles used by `par_transpose`.
+const TILE: usize = 32;
+
+/// Transpose the `rows × cols` matrix `src` into the `cols × rows` matrix
+/// `dest`, both stored in row-major order without padding.
+///
+/// The rows of `dest` are split into bands, each band is processed in
+/// cache-sized square tiles.
+///
+/// Returns [`ParError::LengthMismatch`] if a buffer is smaller than the
+/// matrix, with the length of the buffer as `dest` and the number of elements
+/// of the matrix as `src`.
+pub fn par_transpose<T: Clone + Send + Sync>(
+    src: &[T],
+    dest: &mut [T],
+    rows: usize,
+    cols: usize,
+    num_threads: usize,
+) -> Result<(), ParError> {
+    let len = check_fits(rows.checked_mul(cols), &[src.len(), dest.len()])?;
+    if len == 0 {
+        return Ok(());
+    }
+    let out = Grid::new(cols, rows);
+    let bands = out.bands(0, num_threads);
+    let parts = split::split_ranges(&mut dest[..len], &bands);
+    let chunks = bands.into_iter().zip(parts).collect();
+    exec::run_chunks(num_threads, chunks, |(r, d)| {
+        let (j0, j1) = (r.start / rows, r.end / rows);
+        for jt in (j0..j1).step_by(TILE) {
+            for it in (0..rows).step_by(TILE) {
+                for j in jt..(jt + TILE).min(j1) {
+                    let row = &mut d[(j - j0) * rows..(j - j0 + 1) * rows];
+                    for i in it..(it + TILE).min(rows) {
+                        row[i].clone_from(&src[i * cols + j]);
+                    }
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_map_2d_test() -> Result<(), ParError> {
+        // 10 x 7 grid with 3 padding elements per row
+        let grid = Grid::new(10, 7).with_stride(10);
+        let src = vec![1_u32; grid.min_len()];
+        let mut dest = vec![0_u32; grid.min_len()];
+        let cols = grid.cols;
+        let kernel_fun =
+            move |(row, _): (usize, usize), s: &[u32], d: &mut [u32], stride: usize| {
+                for (y, (s, d)) in s.chunks(stride).zip(d.chunks_mut(stride)).enumerate() {
+                    for x in 0..cols {
+                        d[x] = s[x] * ((row + y) * 100 + x) as u32;
+                    }
+                }
+            };
+        for band_rows in [0, 3] {
+            par_map_2d(&src, &mut dest, grid, band_rows, 4, kernel!(kernel_fun))?;
+            for y in 0..grid.rows {
+                for x in 0..grid.stride {
+                    let e = dest.get(y * grid.stride + x).copied();
+                    if x < cols {
+                        assert_eq!(e, Some((y * 100 + x) as u32));
+                    } else if let Some(e) = e {
+                        assert_eq!(e, 0);
+                    }
+                }
+            }
+        }
+        let r = par_map_2d(&src[1..], &mut dest, grid, 0, 4, kernel!(kernel_fun));
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 97, dest: 96 })
+        ));
+        let huge = Grid::new(usize::MAX, 2);
+        let r = par_map_2d(&src, &mut dest, huge, 0, 4, kernel!(kernel_fun));
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: usize::MAX,
+                dest: 97
+            })
+        ));
+        Ok(())
+    }
+    #[test]
+    fn par_rows_test() -> Result<(), ParError> {
+        // 5 x 7 image of u16 with rows every 16 bytes
+        let (width, height) = (5, 7);
+        let mut buf = vec![u16::MAX; 8 * height];
+        let kernel_fun = |ys: Range<usize>, mut rows: Rows<u16>| {
+            assert_eq!(ys.len(), rows.len());
+            for (y, row) in ys.zip(rows.iter_mut()) {
+                for (x, p) in row.iter_mut().enumerate() {
+                    *p = (y * 100 + x) as u16;
+                }
+            }
+        };
+        par_rows(&mut buf, width, height, 16, 3, kernel!(kernel_fun))?;
+        for (y, row) in buf.chunks(8).enumerate() {
+            for (x, p) in row.iter().enumerate() {
+                let expected = if x < width {
+                    (y * 100 + x) as u16
+                } else {
+                    u16::MAX
+                };
+                assert_eq!(*p, expected);
+            }
+        }
+        Ok(())
+    }
+    #[test]
+    fn par_transpose_test() -> Result<(), ParError> {
+        let (rows, cols) = (45, 70);
+        let src: Vec<usize> = (0..rows * cols).collect();
+        let mut dest = vec![0; src.len()];
+        par_transpose(&src, &mut dest, rows, cols, 3)?;
+        for i in 0..rows {
+            for j in 0..cols {
+                assert_eq!(dest[j * rows + i], src[i * cols + j]);
+            }
+        }
+        let r = par_transpose(&src, &mut dest[1..], rows, cols, 3);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: 3150,
+                dest: 3149
+            })
+        ));
+        let r = par_transpose(&src, &mut dest, usize::MAX, 2, 3);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: usize::MAX,
+                ..
+            })
+        ));
+        // empty matrix
+        par_transpose(&src, &mut dest, 0, usize::MAX, 3)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Grouping of runs of consecutive elements.
+
+use crate::{exec, split, ParError};
+use std::ops::Range;
+use std::sync::Arc;
+
+type SameFun<'a, T> = dyn Fn(&T, &T) -> bool + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Ranges of the maximal runs of consecutive elements for which `same`
+/// returns `true` on every adjacent pair, same as `slice::chunk_by`.
+///
+/// Each thread groups its own sub-range, then the runs straddling the
+/// boundaries between sub-ranges are stitched together.
+pub fn par_group_by<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    same: Arc<SameFun<T>>,
+) -> Result<Vec<Range<usize>>, ParError> {
+    let groups = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(r, s)| {
+        let mut start = r.start;
+        s.chunk_by(|a, b| same(a, b))
+            .map(|g| {
+                start += g.len();
+                start - g.len()..start
+            })
+            .collect::<Vec<_>>()
+    })?;
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for g in groups {
+        let mut g = g.into_iter();
+        if let Some(first) = g.next() {
+            match runs.last_mut() {
+                // join the first run with the last one of the previous
+                // sub-range
+                Some(last) if same(&src[last.end - 1], &src[first.start]) => last.end = first.end,
+                _ => runs.push(first),
+            }
+        }
+        runs.extend(g);
+    }
+    Ok(runs)
+}
+
+//-----------------------------------------------------------------------------
+/// Run-length encoding: each run of equal consecutive elements as the
+/// element and the run length, see [`par_group_by`].
+pub fn par_rle<T: PartialEq + Clone + Sync>(
+    src: &[T],
+    num_threads: usize,
+) -> Result<Vec<(T, usize)>, ParError> {
+    let runs = par_group_by(src, num_threads, Arc::new(|a: &T, b: &T| a == b))?;
+    Ok(runs
+        .into_iter()
+        .map(|r| (src[r.start].clone(), r.len()))
+        .collect())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_group_by_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1000).map(|i| i / 7 + u32::from(i % 100 == 0)).collect();
+        let expected: Vec<Range<usize>> = {
+            let mut start = 0;
+            src.chunk_by(|a, b| a == b)
+                .map(|g| {
+                    start += g.len();
+                    start - g.len()..start
+                })
+                .collect()
+        };
+        for n in [1, 3, 8, 64] {
+            assert_eq!(par_group_by(&src, n, kernel!(|a, b| a == b))?, expected);
+        }
+        // ascending runs
+        let src = [1, 2, 3, 1, 2, 1, 5, 6, 7, 8];
+        let runs = par_group_by(&src, 4, kernel!(|a: &i32, b: &i32| a < b))?;
+        assert_eq!(runs, [0..3, 3..5, 5..10]);
+        assert!(par_group_by(&[0_u8; 0], 4, kernel!(|a, b| a == b))?.is_empty());
+        Ok(())
+    }
+    #[test]
+    fn par_rle_test() -> Result<(), ParError> {
+        let src = b"aaaabbbcccccccd";
+        let rle = par_rle(src, 4)?;
+        assert_eq!(rle, [(b'a', 4), (b'b', 3), (b'c', 7), (b'd', 1)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Checksums computed in parallel.
+
+use crate::ParError;
+
+//-----------------------------------------------------------------------------
+/// Checksum or hash whose value over a concatenation can be computed from
+/// the values over the parts, as required by [`par_hash`].
+///
+/// External algorithms can be plugged in, e.g. a Merkle tree over the
+/// SHA-256 digests of the parts.
+pub trait CombinableHash: Sync {
+    /// Value of the hash.
+    type Output: Send;
+    /// Hash of `data`.
+    fn hash(&self, data: &[u8]) -> Self::Output;
+    /// Hash of the concatenation of the data hashed into `a` and `b`, where
+    /// `b_len` is the length in bytes of the data hashed into `b`.
+    fn combine(&self, a: Self::Output, b: Self::Output, b_len: u64) -> Self::Output;
+}
+
+//-----------------------------------------------------------------------------
+/// Hash `data` with `hasher`: each thread hashes its own sub-range and the
+/// partial hashes are combined in sub-range order.
+///
+/// ```rust,ignore
+/// let crc = par_hash(&file_contents, 8, &Crc32)?;
+/// ```
+pub fn par_hash<H: CombinableHash>(
+    data: &[u8],
+    num_threads: usize,
+    hasher: &H,
+) -> Result<H::Output, ParError> {
+    let (_, h) = crate::reduce::reduce(
+        data,
+        num_threads,
+        (0, hasher.hash(&[])),
+        |s| (s.len() as u64, hasher.hash(s)),
+        |(la, a), (lb, b)| (la + lb, hasher.combine(a, b, lb)),
+    )?;
+    Ok(h)
+}
+
+//-----------------------------------------------------------------------------
+// CRC-32
+//-----------------------------------------------------------------------------
+// Reversed IEEE 802.3 polynomial.
+const CRC32_POLY: u32 = 0xedb8_8320;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                CRC32_POLY ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE 802.3) as computed by zlib, combined with the
+/// `crc32_combine` algorithm.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crc32;
+
+impl CombinableHash for Crc32 {
+    type Output = u32;
+    fn hash(&self, data: &[u8]) -> u32 {
+        !data.iter().fold(!0, |c, b| {
+            CRC32_TABLE[((c ^ u32::from(*b)) & 0xff) as usize] ^ (c >> 8)
+        })
+    }
+    fn combine(&self, a: u32, b: u32, b_len: u64) -> u32 {
+        // `a` is advanced over `b_len` zero bytes by repeatedly squaring the
+        // operator appending one zero bit
+        let mut odd = [0_u32; 32];
+        odd[0] = CRC32_POLY;
+        for (n, o) in odd.iter_mut().enumerate().skip(1) {
+            *o = 1 << (n - 1);
+        }
+        let mut even = gf2_square(&odd);
+        odd = gf2_square(&even);
+        let (mut crc, mut len) = (a, b_len);
+        while len != 0 {
+            even = gf2_square(&odd);
+            if len & 1 != 0 {
+                crc = gf2_times(&even, crc);
+            }
+            len >>= 1;
+            if len == 0 {
+                break;
+            }
+            odd = gf2_square(&even);
+            if len & 1 != 0 {
+                crc = gf2_times(&odd, crc);
+            }
+            len >>= 1;
+        }
+        crc ^ b
+    }
+}
+
+// Product of a GF(2) matrix and a vector.
+fn gf2_times(mat: &[u32; 32], mut vec: u32) -> u32 {
+    let mut sum = 0;
+    let mut i = 0;
+    while vec != 0 {
+        if vec & 1 != 0 {
+            sum ^= mat[i];
+        }
+        vec >>= 1;
+        i += 1;
+    }
+    sum
+}
+
+fn gf2_square(mat: &[u32; 32]) -> [u32; 32] {
+    mat.map(|row| gf2_times(mat, row))
+}
+
+//-----------------------------------------------------------------------------
+// Adler-32
+//-----------------------------------------------------------------------------
+const ADLER_BASE: u64 = 65521;
+
+/// Adler-32 checksum as computed by zlib.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Adler32;
+
+impl CombinableHash for Adler32 {
+    type Output = u32;
+    fn hash(&self, data: &[u8]) -> u32 {
+        let (mut a, mut b) = (1_u64, 0_u64);
+        // the sums cannot overflow before the reduction
+        for block in data.chunks(4096) {
+            for e in block {
+                a += u64::from(*e);
+                b += a;
+            }
+            a %= ADLER_BASE;
+            b %= ADLER_BASE;
+        }
+        (b << 16 | a) as u32
+    }
+    fn combine(&self, a: u32, b: u32, b_len: u64) -> u32 {
+        let rem = b_len % ADLER_BASE;
+        let (a1, a2) = (u64::from(a & 0xffff), u64::from(a >> 16));
+        let (b1, b2) = (u64::from(b & 0xffff), u64::from(b >> 16));
+        let sum1 = (a1 + b1 + ADLER_BASE - 1) % ADLER_BASE;
+        let sum2 = (rem * a1 + a2 + b2 + ADLER_BASE - rem) % ADLER_BASE;
+        (sum2 << 16 | sum1) as u32
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn crc32_test() -> Result<(), ParError> {
+        assert_eq!(Crc32.hash(b"123456789"), 0xcbf4_3926);
+        assert_eq!(Crc32.hash(b""), 0);
+        let data: Vec<u8> = (0..100_000_u32).map(|i| (i * 31 % 251) as u8).collect();
+        let expected = Crc32.hash(&data);
+        for n in [1, 3, 8] {
+            assert_eq!(par_hash(&data, n, &Crc32)?, expected);
+        }
+        assert_eq!(par_hash(&[], 4, &Crc32)?, 0);
+        Ok(())
+    }
+    #[test]
+    fn adler32_test() -> Result<(), ParError> {
+        assert_eq!(Adler32.hash(b"Wikipedia"), 0x11e6_0398);
+        let data = vec![0xff_u8; 100_000];
+        let expected = Adler32.hash(&data);
+        for n in [1, 3, 8] {
+            assert_eq!(par_hash(&data, n, &Adler32)?, expected);
+        }
+        Ok(())
+    }
+}
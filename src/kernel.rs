@@ -0,0 +1,90 @@
+//! Kernel traits.
+
+//-----------------------------------------------------------------------------
+/// Kernel reading a sub-range of the source and writing the matching
+/// sub-range of the destination, accepted by [`par_map`](crate::par_map),
+/// [`par_map_to`](crate::par_map_to) and their variants.
+///
+/// Implemented by every `Fn(&[S], &mut [D]) + Send + Sync` closure. Structs
+/// can implement it to carry their parameters as fields and be tested on
+/// their own:
+///
+/// ```rust,ignore
+/// struct Scale(f32);
+/// impl Kernel<f32> for Scale {
+///     fn run(&self, src: &[f32], dest: &mut [f32]) {
+///         for (d, s) in dest.iter_mut().zip(src) {
+///             *d = s * self.0;
+///         }
+///     }
+/// }
+/// par_map(&src, &mut dest, 8, Arc::new(Scale(2.0)))?;
+/// ```
+pub trait Kernel<S, D = S>: Send + Sync {
+    /// Process a sub-range, `src` and `dest` have the same length.
+    fn run(&self, src: &[S], dest: &mut [D]);
+}
+
+impl<S, D, F> Kernel<S, D> for F
+where
+    F: Fn(&[S], &mut [D]) + Send + Sync + ?Sized,
+{
+    fn run(&self, src: &[S], dest: &mut [D]) {
+        self(src, dest)
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Kernel modifying a sub-range in place, accepted by
+/// [`par_in_place_map`](crate::par_in_place_map) and its variants; see
+/// [`Kernel`].
+pub trait InPlaceKernel<T>: Send + Sync {
+    /// Process a sub-range.
+    fn run(&self, dest: &mut [T]);
+}
+
+impl<T, F> InPlaceKernel<T> for F
+where
+    F: Fn(&mut [T]) + Send + Sync + ?Sized,
+{
+    fn run(&self, dest: &mut [T]) {
+        self(dest)
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{par_in_place_map, par_map, ParError};
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    struct Scale(u32);
+    impl Kernel<u32> for Scale {
+        fn run(&self, src: &[u32], dest: &mut [u32]) {
+            for (d, s) in dest.iter_mut().zip(src) {
+                *d = s * self.0;
+            }
+        }
+    }
+    impl InPlaceKernel<u32> for Scale {
+        fn run(&self, dest: &mut [u32]) {
+            dest.iter_mut().for_each(|e| *e *= self.0);
+        }
+    }
+    #[test]
+    fn kernel_test() -> Result<(), ParError> {
+        let k = Scale(3);
+        let mut d = [0; 2];
+        Kernel::run(&k, &[1, 2], &mut d);
+        assert_eq!(d, [3, 6]);
+        let src: Vec<u32> = (0..100).collect();
+        let mut dest = vec![0; 100];
+        par_map(&src, &mut dest, 4, Arc::new(Scale(2)))?;
+        par_in_place_map(&mut dest, 3, Arc::new(Scale(5)))?;
+        assert!(dest.iter().zip(&src).all(|(d, s)| *d == s * 10));
+        Ok(())
+    }
+}
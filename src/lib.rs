@@ -1,27 +1,86 @@
 //! Parallel execution of functions on sub-ranges of sequences.
 //!
 //! Supports both in-place and copy operations.
-//! The provided functions accept the number of threads to spawn an `Fn` 
+//! The provided functions accept the number of threads to spawn an `Fn`
 //! object with the following signatures:
 //!
 //! ### Copy and map
 //! ```rust,ignore
 //! Fn(&[T], &mut [T]);
 //! ````
+//! ### Copy and map to a different element type
+//! ```rust,ignore
+//! Fn(&[S], &mut [D]);
+//! ````
 //! ### In-place modification
 //! ```rust,ignore
 //! Fn(&mut [T]);
 //! ````
-//! All the functions require the `Fn` object to be wrapped inside an `std::sync::Arc`
-//! instance to allow it to be passed across threads.
+//! The functions require the `Fn` object, or a [`Kernel`] implementation, to be
+//! wrapped inside an `std::sync::Arc` instance to allow it to be passed across
+//! threads; the `*_fn` variants such as [`par_map_fn`] take a closure by value.
 //! A simple `kernel!` macro is provided which wraps whatever is passed to it with an
 //! `Arc` object.
 //!
+//! Passing `0` as the number of threads uses all the available hardware threads.
+//!
+//! Work is run on scoped threads: kernels may borrow data from the calling scope
+//! and elements only need to be `Send + Sync`.
+//!
+//! ## Thread safety
+//!
+//! Thread safety is checked at compile time, the crate does not implement
+//! `Send` or `Sync` for any user type: elements read by several threads must be
+//! `Sync`, elements written must be `Send` and kernels must be `Sync`, `Send`
+//! too when wrapped in an `Arc`. Sequences of `Rc` are rejected:
+//!
+//! ```compile_fail,E0277
+//! use par_seq::*;
+//! use std::rc::Rc;
+//! let src = vec![Rc::new(1)];
+//! let mut dest = src.clone();
+//! par_map_fn(&src, &mut dest, 2, |s, d| d.clone_from_slice(s));
+//! ```
+//!
+//! and so are kernels sharing non thread-safe state:
+//!
+//! ```compile_fail,E0277
+//! use par_seq::*;
+//! use std::cell::Cell;
+//! let calls = Cell::new(0);
+//! let mut dest = vec![0_u8; 64];
+//! par_in_place_map_fn(&mut dest, 2, |_| calls.set(calls.get() + 1));
+//! ```
+//!
+//! ```compile_fail,E0277
+//! use par_seq::*;
+//! use std::rc::Rc;
+//! let offset = Rc::new(1_u8);
+//! let mut dest = vec![0_u8; 64];
+//! par_in_place_map(&mut dest, 2, kernel!(move |d: &mut [u8]| d[0] += *offset));
+//! ```
+//!
+//! Reductions such as [`par_reduce`] combine the partial results in sequence
+//! order: the result of a sub-range is always the left operand of the
+//! combination with the result of the next one, whatever the number of
+//! threads, scheduling or chunking and the order in which the sub-ranges
+//! complete. Combiners only need to be associative, e.g. string concatenation
+//! or matrix products. Likewise, the functions producing one output per
+//! sub-range or per matching element, such as [`par_map_chunks`] or
+//! [`par_filter`], return them in input order, and [`par_stream`] sends its
+//! results in input order unless configured with
+//! [`ParConfigBuilder::unordered`]. Floating-point sums still depend on how
+//! the sequence is split; the `_with` reductions such as [`par_sum_with`] are
+//! reproducible bit for bit with [`ParConfigBuilder::deterministic`].
+//!
+//! Errors are reported as [`ParError`]: a panicking kernel is attributed to its
+//! sub-range, with the panic message and the number of completed sub-ranges.
+//!
 //! ## Examples
 //!
 //!```rust,ignore
 //!    // copy and map
-//!    fn par_map_test() -> std::thread::Result<()> {
+//!    fn par_map_test() -> Result<(), ParError> {
 //!        let len = 64;
 //!        let src = vec![0_u8; len];
 //!        let mut dest = vec![0_u8; len];
@@ -40,7 +99,7 @@
 //!        Ok(())
 //!    }
 //!    // in-place modification
-//!    fn par_in_place_map_test() -> std::thread::Result<()> {
+//!    fn par_in_place_map_test() -> Result<(), ParError> {
 //!        let len = 64;
 //!        let mut dest = vec![0_u8; len];
 //!        let x = 1;
@@ -57,134 +116,633 @@
 //!        }
 //!        Ok(())
 //!    }
+//!
+//! ## `no_std`
+//!
+//! Disabling the default `std` feature builds the crate with `core` and
+//! `alloc` only: the sequence functions, [`par_scan`] and [`ParConfig`] remain
+//! available and the workers run on the [`Spawner`] selected with
+//! [`ParConfigBuilder::spawner`], or serially on the calling thread if none is
+//! set. Kernel panics are not caught and `0` threads selects a single thread.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+// the test harness links `std` in any case
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "std")]
+mod affinity;
+#[cfg(feature = "std")]
+mod bytes;
+mod cancel;
+mod channels;
+#[cfg(feature = "std")]
+mod chunk;
+#[cfg(feature = "std")]
+mod codec;
+mod config;
+#[cfg(feature = "std")]
+mod context;
+mod copy;
+#[cfg(feature = "std")]
+mod distinct;
+mod error;
+mod exec;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+mod file;
+#[cfg(feature = "std")]
+mod filter;
+#[cfg(feature = "std")]
+mod future;
+mod gather;
+#[cfg(feature = "std")]
+mod grid;
+#[cfg(feature = "std")]
+mod group;
+#[cfg(feature = "std")]
+mod hash;
+mod kernel;
+#[cfg(feature = "std")]
+mod linalg;
+pub mod monoid;
+mod pipeline;
+pub mod prelude;
+mod random;
+#[cfg(feature = "std")]
+mod reduce;
+mod reverse;
+#[cfg(feature = "std")]
+mod search;
+#[cfg(feature = "std")]
+mod slice_ext;
+#[cfg(feature = "std")]
+mod sort;
+mod spawn;
+mod split;
+#[cfg(feature = "std")]
+mod state;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod stencil;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod strided;
+#[cfg(feature = "std")]
+mod uninit;
+#[cfg(feature = "std")]
+mod window;
+#[cfg(feature = "std")]
+mod zip;
+#[cfg(feature = "std")]
+pub use affinity::numa_nodes;
+#[cfg(feature = "std")]
+pub use bytes::{par_count_pattern, par_find_pattern, par_split};
+pub use cancel::CancelToken;
+pub use channels::{par_deinterleave, par_interleave};
+#[cfg(feature = "std")]
+pub use chunk::{par_chunks_for_each, Chunk};
+#[cfg(feature = "std")]
+pub use codec::{par_decode_chunks, par_encode_chunks, ChunkCodec};
+pub use config::{ParConfig, ParConfigBuilder};
+#[cfg(feature = "std")]
+pub use context::{par_in_place_map_ctx, par_map_ctx};
+pub use copy::{par_clone_from, par_copy, par_fill};
+#[cfg(feature = "std")]
+pub use distinct::{par_dedup_sorted, par_unique_count};
+pub use error::{
+    ChunkError, ChunkPanic, CodecError, IndexOutOfBounds, PanicError, ParError, ScatterError,
+};
+pub use exec::{Chunking, PanicPolicy, Scheduling, ThreadPriority};
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+pub use file::{par_in_place_map_file, par_map_file, Plain};
+#[cfg(feature = "std")]
+pub use filter::{par_filter, par_positions, par_retain};
+#[cfg(feature = "std")]
+pub use future::{
+    par_in_place_map_async, par_in_place_map_spawn, par_map_async, par_map_spawn, ParFuture,
+    ParHandle,
+};
+pub use gather::{par_gather, par_scatter, Conflict};
+#[cfg(feature = "std")]
+pub use grid::{par_map_2d, par_rows, par_transpose, Grid, Rows};
+#[cfg(feature = "std")]
+pub use group::{par_group_by, par_rle};
+#[cfg(feature = "std")]
+pub use hash::{par_hash, Adler32, CombinableHash, Crc32};
+pub use kernel::{InPlaceKernel, Kernel};
+#[cfg(feature = "std")]
+pub use linalg::{par_axpy, par_dot, par_dot_with, par_matmul, par_norm, par_norm_with, Float};
+pub use monoid::Monoid;
+pub use pipeline::Pipeline;
+pub use random::{par_random_fill, par_sample, par_shuffle, Rng};
+#[cfg(feature = "std")]
+pub use reduce::{
+    par_count, par_fold, par_histogram, par_map_chunks, par_map_reduce, par_max, par_min,
+    par_minmax, par_reduce, par_reduce_monoid, par_reduce_with, par_sum, par_sum_compensated,
+    par_sum_with,
+};
+pub use reverse::{par_reverse, par_rotate_left, par_rotate_right};
+#[cfg(feature = "std")]
+pub use search::{par_all, par_any, par_cmp, par_eq, par_find, par_search_many};
+#[cfg(feature = "std")]
+pub use slice_ext::ParSliceExt;
+#[cfg(feature = "std")]
+pub use sort::{
+    par_merge, par_merge_by, par_sort, par_sort_by, par_sort_by_key, par_sort_unstable, par_top_k,
+};
+#[cfg(feature = "std")]
+pub use spawn::StdSpawner;
+pub use spawn::{Job, Spawner};
+#[cfg(feature = "std")]
+pub use state::{par_in_place_map_stateful, par_map_stateful};
+#[cfg(feature = "std")]
+pub use stats::{ParStats, StatsCollector, WorkerStats};
+#[cfg(feature = "std")]
+pub use stencil::par_stencil;
+#[cfg(feature = "std")]
+pub use stream::{par_stream, par_stream_with};
+#[cfg(feature = "std")]
+pub use strided::{par_in_place_strided, StridedSliceMut};
+#[cfg(feature = "std")]
+pub use uninit::{par_map_collect, par_map_uninit};
+#[cfg(feature = "std")]
+pub use window::{par_in_place_windows, WindowMerge};
+#[cfg(feature = "std")]
+pub use zip::{par_map3, par_map4, par_map5, par_zip_map};
 
-// Need to move pointer to buffer across threads
 //-----------------------------------------------------------------------------
-struct Movable<T>(*const T);
-impl<T> Movable<T> {
-    fn get(&self) -> Option<*const T> {
-        if self.0.is_null() {
-            return None;
-        }
-        Some(self.0)
+// Callable objects shared across threads, 1 and 2 arg versions
+//-----------------------------------------------------------------------------
+type KernelFun2<'a, T> = KernelFunTo<'a, T, T>;
+type KernelFunTo<'a, S, D> = dyn Fn(&[S], &mut [D]) + Send + Sync + 'a;
+type ReadKernelFun<'a, T> = dyn Fn(&[T]) + Send + Sync + 'a;
+// Indexed versions receiving chunk index and offset of the chunk in the sequence
+type IndexedKernelFun2<'a, T> = dyn Fn(usize, usize, &[T], &mut [T]) + Send + Sync + 'a;
+type IndexedKernelFun1<'a, T> = dyn Fn(usize, usize, &mut [T]) + Send + Sync + 'a;
+// Fallible versions
+type TryKernelFun2<'a, T, E> = dyn Fn(&[T], &mut [T]) -> Result<(), E> + Send + Sync + 'a;
+type TryKernelFun1<'a, T, E> = dyn Fn(&mut [T]) -> Result<(), E> + Send + Sync + 'a;
+// Element-wise version returning the destination element
+#[cfg(feature = "std")]
+type ElemFun<'a, S, D> = dyn Fn(&S) -> D + Send + Sync + 'a;
+//-----------------------------------------------------------------------------
+// Reduction callables
+//-----------------------------------------------------------------------------
+#[cfg(feature = "std")]
+type ChunkFun<'a, T, R> = dyn Fn(&[T]) -> R + Send + Sync + 'a;
+type CombineFun<'a, R> = dyn Fn(R, R) -> R + Send + Sync + 'a;
+#[cfg(feature = "std")]
+type FactoryFun<'a, A> = dyn Fn() -> A + Send + Sync + 'a;
+#[cfg(feature = "std")]
+type FoldFun<'a, A, T> = dyn Fn(A, &T) -> A + Send + Sync + 'a;
+#[cfg(feature = "std")]
+type PredFun<'a, T> = dyn Fn(&T) -> bool + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Simple macro which wraps expression with `Arc` object.
+#[macro_export]
+macro_rules! kernel {
+    ( $x:expr ) => {{
+        $crate::__private::Arc::new($x)
+    }};
+}
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::sync::Arc;
+}
+
+//-----------------------------------------------------------------------------
+/// Number of threads used when `0` is passed as the number of threads:
+/// the value returned by `std::thread::available_parallelism`, or `1` if it
+/// cannot be determined.
+#[cfg(feature = "std")]
+pub fn available_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Number of threads used when `0` is passed as the number of threads:
+/// always `1` without `std`.
+#[cfg(not(feature = "std"))]
+pub fn available_threads() -> usize {
+    1
+}
+
+// Replace `0` with the number of available hardware threads.
+fn resolve_threads(num_threads: usize) -> usize {
+    if num_threads == 0 {
+        available_threads()
+    } else {
+        num_threads
     }
 }
 
-struct MovableMut<T>(*mut T);
-impl<T> MovableMut<T> {
-    fn get(&self) -> Option<*mut T> {
-        if self.0.is_null() {
-            return None;
-        }
-        Some(self.0)
+// `ParError::LengthMismatch` unless source and destination have the same
+// length.
+fn check_len<E>(src: usize, dest: usize) -> Result<(), ParError<E>> {
+    if src == dest {
+        Ok(())
+    } else {
+        Err(ParError::LengthMismatch { src, dest })
     }
 }
 
-unsafe impl<T> Send for Movable<T> {}
-unsafe impl<T> Send for MovableMut<T> {}
+// Turn per-chunk kernel results into a `ParError::Kernel` listing the failed
+// sub-ranges.
+fn chunk_errors<E>(results: Vec<(Range<usize>, Result<(), E>)>) -> Result<(), ParError<E>> {
+    let errors: Vec<_> = results
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (range, r))| {
+            r.err().map(|error| ChunkError {
+                index,
+                range,
+                error,
+            })
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ParError::Kernel(errors))
+    }
+}
 
 //-----------------------------------------------------------------------------
-// Structs to move callable objects across threads, 1 and 2 arg versions
+/// Map element from source sequence into element in destination sequence.
+///
+/// The sequences must have the same length, otherwise
+/// [`ParError::LengthMismatch`] is returned before running any kernel.
+pub fn par_map<T: Send + Sync, K: Kernel<T> + ?Sized>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    par_map_to(src, dest, num_threads, fr)
+}
+
 //-----------------------------------------------------------------------------
-type KernelFun2<T> = dyn Fn(&[T], &mut [T]);
-struct FnMove2<T> {
-    f: std::sync::Arc<KernelFun2<T>>,
+/// Map element from source sequence into element of a different type in
+/// destination sequence.
+pub fn par_map_to<S: Sync, D: Send, K: Kernel<S, D> + ?Sized>(
+    src: &[S],
+    dest: &mut [D],
+    num_threads: usize,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    par_map_to_fn(src, dest, num_threads, |s, d| fr.run(s, d))
 }
-impl<T> FnMove2<T> {
-    fn call(&self, src: &[T], dest: &mut [T]) {
-        (self.f)(src, dest);
-    }
+
+//-----------------------------------------------------------------------------
+/// Modify sequence element in-place.
+pub fn par_in_place_map<T: Send, K: InPlaceKernel<T> + ?Sized>(
+    dest: &mut [T],
+    num_threads: usize,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    par_in_place_map_fn(dest, num_threads, |d| fr.run(d))
 }
-unsafe impl<T> Send for FnMove2<T> {}
+
 //-----------------------------------------------------------------------------
-type KernelFun1<T> = dyn Fn(&mut [T]);
-struct FnMove1<T> {
-    f: std::sync::Arc<KernelFun1<T>>,
+/// Same as [`par_map`] taking the kernel by value: the closure is called
+/// directly, without `Arc` nor dynamic dispatch, and only needs to be `Sync`.
+///
+/// ```rust,ignore
+/// let k = 3;
+/// par_map_fn(&src, &mut dest, 4, |s, d| {
+///     d.iter_mut().zip(s).for_each(|(d, s)| *d = s * k)
+/// })?;
+/// ```
+pub fn par_map_fn<T, F>(src: &[T], dest: &mut [T], num_threads: usize, f: F) -> Result<(), ParError>
+where
+    T: Send + Sync,
+    F: Fn(&[T], &mut [T]) + Sync,
+{
+    par_map_to_fn(src, dest, num_threads, f)
 }
-impl<T> FnMove1<T> {
-    fn call(&self, dest: &mut [T]) {
-        (self.f)(dest);
-    }
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map_to`] taking the kernel by value, see [`par_map_fn`].
+pub fn par_map_to_fn<S, D, F>(
+    src: &[S],
+    dest: &mut [D],
+    num_threads: usize,
+    f: F,
+) -> Result<(), ParError>
+where
+    S: Sync,
+    D: Send,
+    F: Fn(&[S], &mut [D]) + Sync,
+{
+    check_len(src.len(), dest.len())?;
+    exec::run_chunks(
+        num_threads,
+        split::chunks((src, dest), num_threads),
+        |(_, (s, d))| f(s, d),
+    )?;
+    Ok(())
 }
-unsafe impl<T> Send for FnMove1<T> {}
 
 //-----------------------------------------------------------------------------
-/// Simple macro which wraps expression with `Arc` object.
-#[macro_export]
-macro_rules! kernel {
-    ( $x:expr ) => {{
-        std::sync::Arc::new($x)
-    }};
+/// Same as [`par_in_place_map`] taking the kernel by value, see
+/// [`par_map_fn`].
+pub fn par_in_place_map_fn<T, F>(dest: &mut [T], num_threads: usize, f: F) -> Result<(), ParError>
+where
+    T: Send,
+    F: Fn(&mut [T]) + Sync,
+{
+    exec::run_chunks(num_threads, split::chunks(dest, num_threads), |(_, d)| f(d))?;
+    Ok(())
 }
 
 //-----------------------------------------------------------------------------
-/// Map element from source sequence into element in destination sequence.
-pub fn par_map<T: 'static>(
+/// Run kernel on each sub-range of a read-only sequence, for side effects only.
+pub fn par_for_each<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    fr: Arc<ReadKernelFun<T>>,
+) -> Result<(), ParError> {
+    exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| fr(s))?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`], the kernel also receives the index of the sub-range
+/// and the offset of its first element in the sequence:
+/// `Fn(chunk_index, global_offset, &[T], &mut [T])`.
+pub fn par_map_indexed<T: Send + Sync>(
     src: &[T],
     dest: &mut [T],
     num_threads: usize,
-    fr: std::sync::Arc<KernelFun2<T>>,
-) -> std::thread::Result<()> {
-    let mut th = vec![];
-    let chunk_size = (src.len() + num_threads - 1) / num_threads;
-    let last_chunk_size = src.len() - (chunk_size * (num_threads - 1));
-    for i in 0..num_threads {
-        unsafe {
-            let idx = (chunk_size * i) as isize;
-            let cs = if i < num_threads - 1 {
-                chunk_size
-            } else {
-                last_chunk_size
-            };
-            let s = Movable(src.as_ptr().offset(idx));
-            let d = MovableMut(dest.as_mut_ptr().offset(idx));
-            let k = FnMove2 { f: fr.clone() };
-            th.push(std::thread::spawn(move || {
-                let src = std::slice::from_raw_parts(s.get().unwrap(), cs);
-                let mut dst = std::slice::from_raw_parts_mut(d.get().unwrap(), cs);
-                k.call(&src, &mut dst);
-            }));
+    fr: Arc<IndexedKernelFun2<T>>,
+) -> Result<(), ParError> {
+    check_len(src.len(), dest.len())?;
+    let chunks = split::chunks((src, dest), num_threads);
+    exec::run(
+        num_threads,
+        chunks.into_iter().enumerate().collect(),
+        |(i, (r, (s, d)))| fr(i, r.start, s, d),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`], the kernel also receives the index of the
+/// sub-range and the offset of its first element in the sequence:
+/// `Fn(chunk_index, global_offset, &mut [T])`.
+pub fn par_in_place_map_indexed<T: Send>(
+    dest: &mut [T],
+    num_threads: usize,
+    fr: Arc<IndexedKernelFun1<T>>,
+) -> Result<(), ParError> {
+    let chunks = split::chunks(dest, num_threads);
+    exec::run(
+        num_threads,
+        chunks.into_iter().enumerate().collect(),
+        |(i, (r, d))| fr(i, r.start, d),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`] with a fallible kernel; the errors of all the failed
+/// sub-ranges are collected into [`ParError::Kernel`].
+pub fn par_try_map<T: Send + Sync, E: Send>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    fr: Arc<TryKernelFun2<T, E>>,
+) -> Result<(), ParError<E>> {
+    check_len(src.len(), dest.len())?;
+    let chunks = split::chunks((src, dest), num_threads);
+    let results = exec::run_chunks(num_threads, chunks, |(r, (s, d))| (r, fr(s, d)))?;
+    chunk_errors(results)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`] with a fallible kernel; the errors of all the
+/// failed sub-ranges are collected into [`ParError::Kernel`].
+pub fn par_try_in_place_map<T: Send, E: Send>(
+    dest: &mut [T],
+    num_threads: usize,
+    fr: Arc<TryKernelFun1<T, E>>,
+) -> Result<(), ParError<E>> {
+    let results = exec::run_chunks(num_threads, split::chunks(dest, num_threads), |(r, d)| {
+        (r, fr(d))
+    })?;
+    chunk_errors(results)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_try_map`] with the execution parameters taken from `cfg`.
+///
+/// With the default [`PanicPolicy::FailFast`] the sub-ranges not yet started
+/// when a kernel returns an error are skipped and only the errors of the
+/// sub-ranges run are returned; a small chunk size bounds the work done after
+/// the first error.
+///
+/// ```rust,ignore
+/// let cfg = ParConfig::builder().threads(8).chunk_size(1 << 16).build();
+/// par_try_map_with(&cfg, &records, &mut parsed, kernel!(parse_block))?;
+/// ```
+pub fn par_try_map_with<T: Send + Sync, E: Send>(
+    cfg: &ParConfig,
+    src: &[T],
+    dest: &mut [T],
+    fr: Arc<TryKernelFun2<T, E>>,
+) -> Result<(), ParError<E>> {
+    check_len(src.len(), dest.len())?;
+    try_run_with(cfg, (src, dest), |(s, d)| fr(s, d))
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_try_in_place_map`] with the execution parameters taken from
+/// `cfg`, see [`par_try_map_with`].
+pub fn par_try_in_place_map_with<T: Send, E: Send>(
+    cfg: &ParConfig,
+    dest: &mut [T],
+    fr: Arc<TryKernelFun1<T, E>>,
+) -> Result<(), ParError<E>> {
+    try_run_with(cfg, dest, |d| fr(d))
+}
+
+// Run a fallible kernel on the sub-ranges of `data` selected by `cfg`,
+// skipping the remaining ones after the first error unless all the errors
+// are collected.
+fn try_run_with<P: split::Split, E: Send>(
+    cfg: &ParConfig,
+    data: P,
+    f: impl Fn(P) -> Result<(), E> + Sync,
+) -> Result<(), ParError<E>> {
+    let deadline = cfg.deadline();
+    let fail_fast = cfg.panic_policy() == PanicPolicy::FailFast;
+    let failed = AtomicBool::new(false);
+    let results = exec::run_with(cfg, data, |(r, p)| {
+        if failed.load(Ordering::Relaxed) || cfg.is_stopped(deadline) {
+            return (r, Ok(()));
         }
-    }
-    for t in th {
-        if let Err(e) = t.join() {
-            return Err(e);
+        let res = f(p);
+        if fail_fast && res.is_err() {
+            failed.store(true, Ordering::Relaxed);
         }
-    }
-    Ok(())
+        (r, res)
+    })?;
+    chunk_errors(results)?;
+    cfg.check_stopped(deadline)
 }
 
 //-----------------------------------------------------------------------------
-/// Modify sequence element in-place.
-pub fn par_in_place_map<T: 'static>(
+/// Same as [`par_map`] with the execution parameters taken from `cfg`.
+pub fn par_map_with<T: Send + Sync, K: Kernel<T> + ?Sized>(
+    cfg: &ParConfig,
+    src: &[T],
+    dest: &mut [T],
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    check_len(src.len(), dest.len())?;
+    let deadline = cfg.deadline();
+    exec::run_with(cfg, (src, dest), |(_, (s, d))| {
+        if !cfg.is_stopped(deadline) {
+            fr.run(s, d)
+        }
+    })?;
+    cfg.check_stopped(deadline)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`] with the execution parameters taken from `cfg`.
+pub fn par_in_place_map_with<T: Send, K: InPlaceKernel<T> + ?Sized>(
+    cfg: &ParConfig,
+    dest: &mut [T],
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    let deadline = cfg.deadline();
+    exec::run_with(cfg, dest, |(_, d)| {
+        if !cfg.is_stopped(deadline) {
+            fr.run(d)
+        }
+    })?;
+    cfg.check_stopped(deadline)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`], sub-ranges not yet started when `token` is cancelled
+/// are skipped and [`ParError::Cancelled`] is returned.
+pub fn par_map_cancellable<T: Send + Sync, K: Kernel<T> + ?Sized>(
+    src: &[T],
     dest: &mut [T],
     num_threads: usize,
-    fr: std::sync::Arc<KernelFun1<T>>,
-) -> std::thread::Result<()> {
-    let mut th = vec![];
-    let chunk_size = (dest.len() + num_threads - 1) / num_threads;
-    let last_chunk_size = dest.len() - (chunk_size * (num_threads - 1));
-    for i in 0..num_threads {
-        unsafe {
-            let idx = (chunk_size * i) as isize;
-            let cs = if i < num_threads - 1 {
-                chunk_size
-            } else {
-                last_chunk_size
+    token: &CancelToken,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    let cfg = cancellable_config(num_threads, token);
+    par_map_with(&cfg, src, dest, fr)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`], sub-ranges not yet started when `token` is
+/// cancelled are skipped and [`ParError::Cancelled`] is returned.
+pub fn par_in_place_map_cancellable<T: Send, K: InPlaceKernel<T> + ?Sized>(
+    dest: &mut [T],
+    num_threads: usize,
+    token: &CancelToken,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    let cfg = cancellable_config(num_threads, token);
+    par_in_place_map_with(&cfg, dest, fr)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`], sub-ranges not yet started after `timeout` are skipped
+/// and [`ParError::Timeout`] is returned once the running ones complete.
+#[cfg(feature = "std")]
+pub fn par_map_timeout<T: Send + Sync, K: Kernel<T> + ?Sized>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    timeout: core::time::Duration,
+    fr: Arc<K>,
+) -> Result<(), ParError> {
+    let cfg = ParConfig::builder()
+        .threads(num_threads)
+        .timeout(timeout)
+        .build();
+    par_map_with(&cfg, src, dest, fr)
+}
+
+fn cancellable_config(num_threads: usize, token: &CancelToken) -> ParConfig {
+    ParConfig::builder()
+        .threads(num_threads)
+        .cancel_token(token.clone())
+        .build()
+}
+
+//-----------------------------------------------------------------------------
+/// Kind of prefix scan computed by [`par_scan`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scan {
+    /// `dest[i]` includes `src[i]`.
+    Inclusive,
+    /// `dest[i]` combines the elements before `src[i]`, `dest[0]` is the identity.
+    Exclusive,
+}
+
+/// Parallel prefix scan of `src` into `dest` with the associative operation
+/// `op`.
+///
+/// Each thread scans its sub-range, the sub-range totals are scanned serially
+/// and each thread then combines its elements with the resulting offset.
+///
+/// Returns [`ParError::LengthMismatch`] if `src` and `dest` have different
+/// lengths.
+pub fn par_scan<T: Clone + Send + Sync>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    identity: T,
+    op: Arc<CombineFun<T>>,
+    kind: Scan,
+) -> Result<(), ParError> {
+    check_len(src.len(), dest.len())?;
+    let ranges = split::ranges(src.len(), num_threads);
+    let chunks = split::split_ranges((src, &mut *dest), &ranges);
+    let totals = exec::run(num_threads, chunks, |(s, d)| {
+        let mut acc = identity.clone();
+        for (d, s) in d.iter_mut().zip(s) {
+            let next = op(acc.clone(), s.clone());
+            *d = match kind {
+                Scan::Inclusive => next.clone(),
+                Scan::Exclusive => acc,
             };
-            let d = MovableMut(dest.as_mut_ptr().offset(idx));
-            let k = FnMove1 { f: fr.clone() };
-            th.push(std::thread::spawn(move || {
-                let mut dst = std::slice::from_raw_parts_mut(d.get().unwrap(), cs);
-                k.call(&mut dst);
-            }));
+            acc = next;
         }
+        acc
+    })?;
+    let mut offsets = Vec::with_capacity(totals.len());
+    let mut acc = identity;
+    for t in totals {
+        offsets.push(acc.clone());
+        acc = op(acc, t);
     }
-    for t in th {
-        if  let Err(e) = t.join() {
-            return Err(e);
+    // the first sub-range has an identity offset and is already final
+    let chunks = split::split_ranges(dest, &ranges)
+        .into_iter()
+        .zip(offsets)
+        .skip(1);
+    exec::run(num_threads, chunks.collect(), |(d, off)| {
+        for e in d {
+            *e = op(off.clone(), e.clone());
         }
-    }
+    })?;
     Ok(())
 }
 
@@ -193,41 +751,801 @@ pub fn par_in_place_map<T: 'static>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
     #[test]
-    fn par_map_test() -> std::thread::Result<()> {
+    fn par_map_test() -> Result<(), ParError> {
         let len = 64;
         let src = vec![0_u8; len];
         let mut dest = vec![0_u8; len];
         let x = 1;
         let kernel_fun = move |s: &[u8], d: &mut [u8]| {
-            for i in 0..s.len() {
-                d[i] = s[i] + x;
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = s + x;
             }
         };
-        if let Err(e) = par_map(&src, &mut dest, 3, kernel!(kernel_fun)) {
-            return Err(e);
-        }
+        par_map(&src, &mut dest, 3, kernel!(kernel_fun))?;
         for e in dest {
             assert_eq!(e, 1);
         }
         Ok(())
     }
     #[test]
-    fn par_in_place_map_test() -> std::thread::Result<()> {
+    fn par_in_place_map_test() -> Result<(), ParError> {
         let len = 64;
         let mut dest = vec![0_u8; len];
         let x = 1;
         let kernel_fun = move |d: &mut [u8]| {
-            for i in 0..d.len() {
-                d[i] += x;
+            for e in d {
+                *e += x;
             }
         };
-        if let Err(e) = par_in_place_map(&mut dest, 3, kernel!(kernel_fun)) {
-            return Err(e);
-        }
+        par_in_place_map(&mut dest, 3, kernel!(kernel_fun))?;
         for e in dest {
             assert_eq!(e, 1);
         }
         Ok(())
     }
+    #[test]
+    fn par_map_to_test() -> Result<(), ParError> {
+        let src: Vec<u8> = (0..=255).collect();
+        let mut dest = vec![0_f32; src.len()];
+        let kernel_fun = |s: &[u8], d: &mut [f32]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = *s as f32 / 255.0;
+            }
+        };
+        par_map_to(&src, &mut dest, 3, kernel!(kernel_fun))?;
+        assert_eq!(dest[0], 0.0);
+        assert_eq!(dest[255], 1.0);
+        assert_eq!(dest[51], 0.2);
+        Ok(())
+    }
+    #[test]
+    fn length_mismatch_test() {
+        let src = vec![0_u8; 10];
+        let mut dest = vec![0_u8; 9];
+        let r = par_map(&src, &mut dest, 3, kernel!(|_: &[u8], _: &mut [u8]| {}));
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 10, dest: 9 })
+        ));
+        let r = par_try_map(
+            &src,
+            &mut dest,
+            3,
+            kernel!(|_: &[u8], _: &mut [u8]| Ok::<_, ()>(())),
+        );
+        assert!(matches!(r, Err(ParError::LengthMismatch { .. })));
+        let r = par_scan(
+            &src,
+            &mut dest,
+            3,
+            0,
+            kernel!(|a, b| a + b),
+            Scan::Exclusive,
+        );
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 10, dest: 9 })
+        ));
+        let cfg = ParConfig::builder().threads(2).build();
+        let r = par_map_with(
+            &cfg,
+            &src[..9],
+            &mut dest,
+            kernel!(|_: &[u8], _: &mut [u8]| {}),
+        );
+        assert!(r.is_ok());
+    }
+    #[test]
+    fn par_map_fn_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..100).collect();
+        let mut dest = vec![0; 100];
+        // borrowed state, no `Arc` nor `'static` needed
+        let factors = [2_u32, 3];
+        par_map_fn(&src, &mut dest, 3, |s, d| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = s * factors[0];
+            }
+        })?;
+        par_in_place_map_fn(&mut dest, 4, |d| {
+            d.iter_mut().for_each(|e| *e *= factors[1])
+        })?;
+        assert!(dest.iter().zip(&src).all(|(d, s)| *d == s * 6));
+        let mut bytes = vec![0_u8; 100];
+        par_map_to_fn(&src, &mut bytes, 2, |s, d| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = *s as u8;
+            }
+        })?;
+        assert_eq!(bytes[99], 99);
+        Ok(())
+    }
+    #[test]
+    fn par_for_each_test() -> Result<(), ParError> {
+        let src: Vec<u64> = (1..=100).collect();
+        let sum = std::sync::atomic::AtomicU64::new(0);
+        let s = &sum;
+        let kernel_fun = move |c: &[u64]| {
+            s.fetch_add(c.iter().sum(), std::sync::atomic::Ordering::Relaxed);
+        };
+        par_for_each(&src, 3, kernel!(kernel_fun))?;
+        assert_eq!(sum.into_inner(), 5050);
+        Ok(())
+    }
+    #[test]
+    fn par_map_indexed_test() -> Result<(), ParError> {
+        let src = vec![1_usize; 50];
+        let mut dest = vec![0_usize; src.len()];
+        let kernel_fun = |_: usize, off: usize, s: &[usize], d: &mut [usize]| {
+            for (i, (d, s)) in d.iter_mut().zip(s).enumerate() {
+                *d = s + off + i;
+            }
+        };
+        par_map_indexed(&src, &mut dest, 3, kernel!(kernel_fun))?;
+        assert_eq!(dest, (1..=50).collect::<Vec<_>>());
+        Ok(())
+    }
+    #[test]
+    fn par_in_place_map_indexed_test() -> Result<(), ParError> {
+        let mut dest = vec![(0_usize, 0_usize); 10];
+        let kernel_fun = |c: usize, off: usize, d: &mut [(usize, usize)]| {
+            for (i, e) in d.iter_mut().enumerate() {
+                *e = (c, off + i);
+            }
+        };
+        par_in_place_map_indexed(&mut dest, 2, kernel!(kernel_fun))?;
+        for (i, e) in dest.iter().enumerate() {
+            assert_eq!(*e, (i / 5, i));
+        }
+        Ok(())
+    }
+    #[test]
+    fn par_try_map_test() {
+        let src: Vec<i32> = (0..12).collect();
+        let mut dest = vec![0_i32; src.len()];
+        let kernel_fun = |s: &[i32], d: &mut [i32]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                if *s == 5 || *s == 10 {
+                    return Err(*s);
+                }
+                *d = *s;
+            }
+            Ok(())
+        };
+        match par_try_map(&src, &mut dest, 4, kernel!(kernel_fun)) {
+            Err(ParError::Kernel(errors)) => {
+                let failed: Vec<_> = errors
+                    .iter()
+                    .map(|e| (e.index, e.range.clone(), e.error))
+                    .collect();
+                assert_eq!(failed, vec![(1, 3..6, 5), (3, 9..12, 10)]);
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+    #[test]
+    fn par_try_in_place_map_test() -> Result<(), ParError<String>> {
+        let mut dest = vec![1_u8; 32];
+        let kernel_fun = |d: &mut [u8]| {
+            for e in d {
+                *e = e.checked_mul(2).ok_or_else(|| "overflow".to_string())?;
+            }
+            Ok(())
+        };
+        par_try_in_place_map(&mut dest, 3, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 2));
+        Ok(())
+    }
+    #[test]
+    fn par_try_map_with_test() {
+        use std::sync::atomic::AtomicUsize;
+        let src: Vec<u32> = (0..1000).collect();
+        let mut dest = vec![0; 1000];
+        let runs = Arc::new(AtomicUsize::new(0));
+        let r = runs.clone();
+        let kernel_fun = move |s: &[u32], d: &mut [u32]| {
+            r.fetch_add(1, Ordering::Relaxed);
+            d.copy_from_slice(s);
+            match s[0] % 100 {
+                0 => Err(s[0]),
+                _ => Ok(()),
+            }
+        };
+        let fr: Arc<TryKernelFun2<u32, u32>> = kernel!(kernel_fun);
+        // a single worker runs the sub-ranges in order and stops at the first
+        let cfg = ParConfig::builder().threads(1).chunk_size(10).build();
+        match par_try_map_with(&cfg, &src, &mut dest, fr.clone()) {
+            Err(ParError::Kernel(e)) => assert_eq!((e.len(), e[0].index, e[0].error), (1, 0, 0)),
+            r => panic!("unexpected result {r:?}"),
+        }
+        assert_eq!(runs.swap(0, Ordering::Relaxed), 1);
+        let cfg = ParConfig::builder()
+            .threads(4)
+            .chunk_size(10)
+            .panic_policy(PanicPolicy::CollectAll)
+            .build();
+        match par_try_map_with(&cfg, &src, &mut dest, fr) {
+            Err(ParError::Kernel(e)) => {
+                let failed: Vec<_> = e.iter().map(|e| e.index).collect();
+                assert_eq!(failed, (0..100).step_by(10).collect::<Vec<_>>());
+            }
+            r => panic!("unexpected result {r:?}"),
+        }
+        assert_eq!(runs.load(Ordering::Relaxed), 100);
+        assert_eq!(dest, src);
+        let mut dest = vec![1_u8; 100];
+        let kernel_fun = |d: &mut [u8]| {
+            d.iter_mut()
+                .try_for_each(|e| e.checked_add(1).map(|v| *e = v).ok_or(()))
+        };
+        assert!(par_try_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun)).is_ok());
+    }
+    #[test]
+    fn par_map_cancellable_test() {
+        let src = vec![0_u8; 64];
+        let mut dest = vec![0_u8; 64];
+        let token = CancelToken::new();
+        let t = token.clone();
+        let kernel_fun = move |s: &[u8], d: &mut [u8]| {
+            t.cancel();
+            d.copy_from_slice(s);
+        };
+        assert!(matches!(
+            par_map_cancellable(&src, &mut dest, 4, &token, kernel!(kernel_fun)),
+            Err(ParError::Cancelled)
+        ));
+        let token = CancelToken::new();
+        let kernel_fun = |d: &mut [u8]| d.fill(1);
+        assert!(par_in_place_map_cancellable(&mut dest, 4, &token, kernel!(kernel_fun)).is_ok());
+        assert!(dest.iter().all(|e| *e == 1));
+        // nothing runs once cancelled
+        token.cancel();
+        let kernel_fun = |d: &mut [u8]| d.fill(2);
+        assert!(par_in_place_map_cancellable(&mut dest, 4, &token, kernel!(kernel_fun)).is_err());
+        assert!(dest.iter().all(|e| *e == 1));
+    }
+    #[test]
+    fn par_map_with_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..100).collect();
+        let mut dest = vec![0_u32; src.len()];
+        let cfg = ParConfig::builder().threads(5).build();
+        assert_eq!(cfg.num_threads(), 5);
+        let kernel_fun = |s: &[u32], d: &mut [u32]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = s * 2;
+            }
+        };
+        par_map_with(&cfg, &src, &mut dest, kernel!(kernel_fun))?;
+        par_in_place_map_with(
+            &cfg,
+            &mut dest,
+            kernel!(|d: &mut [u32]| d.iter_mut().for_each(|e| *e += 1)),
+        )?;
+        assert!(dest.iter().enumerate().all(|(i, e)| *e == 2 * i as u32 + 1));
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn edge_cases_test() -> Result<(), ParError> {
+        // more threads than elements
+        let src = vec![1_u8; 3];
+        let mut dest = vec![0_u8; 3];
+        par_map(
+            &src,
+            &mut dest,
+            8,
+            kernel!(|s: &[u8], d: &mut [u8]| d.copy_from_slice(s)),
+        )?;
+        assert_eq!(dest, src);
+        let count = std::sync::atomic::AtomicUsize::new(0);
+        let c = &count;
+        par_in_place_map(
+            &mut dest,
+            8,
+            kernel!(move |_: &mut [u8]| {
+                c.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }),
+        )?;
+        assert_eq!(count.into_inner(), 3);
+        // empty sequences
+        let mut empty: Vec<u8> = Vec::new();
+        par_map(
+            &[],
+            &mut empty,
+            4,
+            kernel!(|_: &[u8], _: &mut [u8]| panic!()),
+        )?;
+        par_in_place_map(&mut empty, 4, kernel!(|_: &mut [u8]| panic!()))?;
+        let sum = par_reduce(&empty, 4, 7, kernel!(|_: &[u8]| 0), kernel!(|a, b| a + b))?;
+        assert_eq!(sum, 7);
+        Ok(())
+    }
+    #[test]
+    fn auto_threads_test() -> Result<(), ParError> {
+        let mut dest = vec![0_u8; 100];
+        par_in_place_map(&mut dest, 0, kernel!(|d: &mut [u8]| d.fill(1)))?;
+        assert!(dest.iter().all(|e| *e == 1));
+        assert!(available_threads() >= 1);
+        assert_eq!(ParConfig::default().num_threads(), available_threads());
+        Ok(())
+    }
+    #[test]
+    fn chunk_size_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1000).collect();
+        let mut dest = vec![0_u32; src.len()];
+        let cfg = ParConfig::builder().threads(3).chunk_size(64).build();
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let c = &calls;
+        let kernel_fun = move |s: &[u32], d: &mut [u32]| {
+            assert!(s.len() <= 64);
+            c.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            d.copy_from_slice(s);
+        };
+        par_map_with(&cfg, &src, &mut dest, kernel!(kernel_fun))?;
+        assert_eq!(calls.into_inner(), 16);
+        assert_eq!(dest, src);
+        Ok(())
+    }
+    #[test]
+    fn cache_aligned_test() -> Result<(), ParError> {
+        let mut dest = vec![0_u16; 1000];
+        let cfg = ParConfig::builder().threads(3).cache_aligned(true).build();
+        assert_eq!(cfg.cache_line_size(), Some(64));
+        // every sub-range but the first starts a line, whatever the alignment
+        // of the sequence
+        for start in [0, 3, 13] {
+            let d = &mut dest[start..];
+            let base = d.as_ptr() as usize;
+            let kernel_fun = move |d: &mut [u16]| {
+                let addr = d.as_ptr() as usize;
+                assert!(addr == base || addr.is_multiple_of(64));
+                d.fill(start as u16 + 1);
+            };
+            par_in_place_map_with(&cfg, d, kernel!(kernel_fun))?;
+            assert!(d.iter().all(|e| *e == start as u16 + 1));
+        }
+        // the destination of a map is aligned
+        let src = vec![1_u16; 1000];
+        let dest_base = dest[1..].as_ptr() as usize;
+        let kernel_fun = move |s: &[u16], d: &mut [u16]| {
+            let addr = d.as_ptr() as usize;
+            assert!(addr == dest_base || addr.is_multiple_of(64));
+            d.copy_from_slice(s);
+        };
+        par_map_with(&cfg, &src[1..], &mut dest[1..], kernel!(kernel_fun))?;
+        assert!(dest[1..].iter().all(|e| *e == 1));
+        Ok(())
+    }
+    #[test]
+    fn simd_width_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1003).collect();
+        let mut dest = vec![0_u32; src.len()];
+        let cfg = ParConfig::builder().threads(4).simd_width(8).build();
+        let kernel_fun = |s: &[u32], d: &mut [u32]| {
+            assert!(s[0].is_multiple_of(8));
+            assert!(s.len().is_multiple_of(8) || s.len() == 3);
+            d.copy_from_slice(s);
+        };
+        par_map_with(&cfg, &src, &mut dest, kernel!(kernel_fun))?;
+        assert_eq!(dest, src);
+        Ok(())
+    }
+    #[test]
+    fn scheduling_test() -> Result<(), ParError> {
+        let schedulings = [
+            Scheduling::Shared,
+            Scheduling::Static,
+            Scheduling::Dynamic,
+            Scheduling::Cyclic,
+        ];
+        let chunkings = [Chunking::Fixed, Chunking::Guided];
+        for (scheduling, chunking) in schedulings.into_iter().zip(chunkings.into_iter().cycle()) {
+            let cfg = ParConfig::builder()
+                .threads(4)
+                .chunk_size(10)
+                .scheduling(scheduling)
+                .chunking(chunking)
+                .build();
+            assert_eq!(cfg.scheduling(), scheduling);
+            let kernel_fun = |d: &mut [usize]| {
+                // uneven per-chunk cost
+                if d[0].is_multiple_of(70) {
+                    std::thread::sleep(std::time::Duration::from_millis(2));
+                }
+                for e in d {
+                    *e += 1;
+                }
+            };
+            let mut dest: Vec<usize> = (0..1000).collect();
+            par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+            assert!(dest.iter().enumerate().all(|(i, e)| *e == i + 1));
+        }
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn adaptive_chunking_test() -> Result<(), ParError> {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let s = seen.clone();
+        let cfg = ParConfig::builder()
+            .threads(4)
+            .chunking(Chunking::Adaptive)
+            .on_chunk(kernel!(move |_, i, r, _| s.lock().unwrap().push((i, r))))
+            .build();
+        assert_eq!(cfg.chunking(), Chunking::Adaptive);
+        let kernel_fun = |s: &[u64], d: &mut [u64]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = (0..*s % 64).sum();
+            }
+        };
+        let src: Vec<u64> = (0..100_000).collect();
+        let mut dest = vec![0; src.len()];
+        par_map_with(&cfg, &src, &mut dest, kernel!(kernel_fun))?;
+        assert!(dest
+            .iter()
+            .zip(&src)
+            .all(|(d, s)| *d == (s % 64) * (s % 64).saturating_sub(1) / 2));
+        // the sub-ranges are numbered in order and cover the sequence
+        let mut seen = seen.lock().unwrap().clone();
+        assert!(seen.len() > 4);
+        seen.sort_by_key(|e| e.0);
+        let mut end = 0;
+        for (i, (j, r)) in seen.into_iter().enumerate() {
+            assert_eq!((i, r.start), (j, end));
+            end = r.end;
+        }
+        assert_eq!(end, src.len());
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn weighted_split_test() -> Result<(), ParError> {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let s = seen.clone();
+        let weights: Vec<u64> = (0..100).collect();
+        let cfg = ParConfig::builder()
+            .threads(4)
+            .weight_fn(kernel!(move |i| weights[i]))
+            .on_chunk(kernel!(move |_, _, r, _| s.lock().unwrap().push(r)))
+            .build();
+        let mut dest = vec![0_u8; 100];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(|d: &mut [u8]| d.fill(1)))?;
+        assert!(dest.iter().all(|e| *e == 1));
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort_by_key(|r| r.start);
+        assert_eq!(seen, [0..51, 51..71, 71..87, 87..100]);
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn cyclic_scheduling_test() -> Result<(), ParError> {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let s = seen.clone();
+        let cfg = ParConfig::builder()
+            .threads(3)
+            .chunk_size(1)
+            .scheduling(Scheduling::Cyclic)
+            .on_chunk(kernel!(move |w, i, _, _| s.lock().unwrap().push((i, w))))
+            .build();
+        let mut dest = vec![0_u8; 10];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(|d: &mut [u8]| d[0] = 1))?;
+        assert!(dest.iter().all(|e| *e == 1));
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert!(seen.iter().all(|(i, w)| i % 3 == *w));
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_hooks_test() -> Result<(), ParError> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        let started = Arc::new(AtomicUsize::new(0));
+        let ended = Arc::new(AtomicUsize::new(0));
+        let (s, e) = (started.clone(), ended.clone());
+        let cfg = ParConfig::builder()
+            .threads(3)
+            .chunk_size(7)
+            .on_thread_start(kernel!(move |_| {
+                s.fetch_add(1, Ordering::Relaxed);
+            }))
+            .on_thread_end(kernel!(move |_| {
+                e.fetch_add(1, Ordering::Relaxed);
+            }))
+            .build();
+        let mut dest = vec![0_u8; 100];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(|d: &mut [u8]| d.fill(1)))?;
+        assert!(dest.iter().all(|e| *e == 1));
+        assert_eq!(started.load(Ordering::Relaxed), 3);
+        assert_eq!(ended.load(Ordering::Relaxed), 3);
+        Ok(())
+    }
+    #[test]
+    fn progress_test() -> Result<(), ParError> {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let s = seen.clone();
+        let cfg = ParConfig::builder()
+            .threads(4)
+            .chunk_size(10)
+            .on_progress(kernel!(move |done, total| {
+                s.lock().unwrap().push((done, total));
+            }))
+            .build();
+        let src = vec![1_u8; 95];
+        let mut dest = vec![0_u8; 95];
+        par_map_with(
+            &cfg,
+            &src,
+            &mut dest,
+            kernel!(|s: &[u8], d: &mut [u8]| d.copy_from_slice(s)),
+        )?;
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, (1..=10).map(|i| (i, 10)).collect::<Vec<_>>());
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn stats_test() -> Result<(), ParError> {
+        let stats = StatsCollector::new();
+        let cfg = ParConfig::builder()
+            .threads(3)
+            .chunk_size(10)
+            .scheduling(Scheduling::Static)
+            .stats(stats.clone())
+            .build();
+        let mut dest = vec![0_u8; 95];
+        let kernel_fun = |d: &mut [u8]| {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            d.fill(1);
+        };
+        par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        let stats = stats.stats().unwrap();
+        assert_eq!(stats.workers.len(), 3);
+        let chunks: Vec<usize> = stats.workers.iter().map(|w| w.chunks).collect();
+        assert_eq!(chunks, [4, 3, 3]);
+        for w in &stats.workers {
+            assert!(w.busy >= std::time::Duration::from_millis(w.chunks as u64));
+            assert_eq!(w.busy + w.wait, stats.elapsed);
+        }
+        assert!(stats.utilization() > 0.0 && stats.utilization() <= 1.0);
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn on_chunk_test() -> Result<(), ParError> {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let s = seen.clone();
+        let cfg = ParConfig::builder()
+            .threads(2)
+            .chunk_size(30)
+            .on_chunk(kernel!(move |w, i, r, _| {
+                s.lock().unwrap().push((i, r, w < 2));
+            }))
+            .build();
+        let mut dest = vec![0_u8; 100];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(|d: &mut [u8]| d.fill(1)))?;
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort_by_key(|e| e.0);
+        assert_eq!(
+            seen,
+            [
+                (0, 0..30, true),
+                (1, 30..60, true),
+                (2, 60..90, true),
+                (3, 90..100, true)
+            ]
+        );
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn par_map_timeout_test() {
+        let src = vec![1_u8; 64];
+        let mut dest = vec![0_u8; 64];
+        let kernel_fun = |s: &[u8], d: &mut [u8]| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            d.copy_from_slice(s);
+        };
+        let r = par_map_timeout(
+            &src,
+            &mut dest,
+            2,
+            std::time::Duration::from_millis(1),
+            kernel!(kernel_fun),
+        );
+        assert!(matches!(r, Err(ParError::Timeout)));
+        let r = par_map_timeout(
+            &src,
+            &mut dest,
+            2,
+            std::time::Duration::from_secs(60),
+            kernel!(kernel_fun),
+        );
+        assert!(r.is_ok());
+        assert_eq!(dest, src);
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn panic_policy_test() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let runs = AtomicUsize::new(0);
+        let kernel_fun = |d: &mut [u32]| {
+            runs.fetch_add(1, Ordering::Relaxed);
+            if d[0].is_multiple_of(20) {
+                panic!("chunk at {}", d[0]);
+            }
+        };
+        let mut dest: Vec<u32> = (0..100).collect();
+        let cfg = ParConfig::builder()
+            .threads(1)
+            .chunk_size(10)
+            .panic_policy(PanicPolicy::CollectAll)
+            .build();
+        match par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun)) {
+            Err(ParError::Panic(e)) => {
+                assert_eq!(e.panics.len(), 5);
+                assert_eq!(e.completed, 5);
+                assert_eq!(e.panics[1].index, Some(2));
+                assert_eq!(e.panics[1].range, Some(20..30));
+            }
+            r => panic!("unexpected {r:?}"),
+        }
+        assert_eq!(runs.swap(0, Ordering::Relaxed), 10);
+        // a single thread stops at the first panic
+        let cfg = ParConfig::builder().threads(1).chunk_size(10).build();
+        let r = par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun));
+        assert!(matches!(r, Err(ParError::Panic(_))));
+        assert_eq!(runs.into_inner(), 1);
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn panic_attribution_test() {
+        let mut dest: Vec<u32> = (0..64).collect();
+        let kernel_fun = |d: &mut [u32]| {
+            if d[0] == 32 {
+                panic!("bad chunk");
+            }
+        };
+        match par_in_place_map(&mut dest, 4, kernel!(kernel_fun)) {
+            Err(ParError::Panic(e)) => {
+                assert_eq!(e.first().index, Some(2));
+                assert_eq!(e.first().range, Some(32..48));
+                assert_eq!(e.message(), Some("bad chunk"));
+                assert_eq!(e.total, 4);
+                assert!(e
+                    .to_string()
+                    .starts_with("chunk 2 32..48 panicked: bad chunk"));
+            }
+            r => panic!("unexpected {r:?}"),
+        }
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn thread_builder_test() -> Result<(), ParError> {
+        let cfg = ParConfig::builder()
+            .threads(2)
+            .thread_name_prefix("worker")
+            .stack_size(64 << 20)
+            .build();
+        // deep recursion needing more than the default 2 MiB stack
+        fn depth(n: u64) -> u64 {
+            let pad = [n; 64];
+            if n == 0 {
+                0
+            } else {
+                std::hint::black_box(pad)[0] - n + 1 + depth(n - 1)
+            }
+        }
+        let kernel_fun = |d: &mut [u64]| {
+            let name = std::thread::current().name().map(|n| n.to_string());
+            assert!(name.is_some_and(|n| n.starts_with("worker-")));
+            d.fill(depth(10_000));
+        };
+        let mut dest = vec![0_u64; 4];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 10_000));
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn serial_fallback_test() -> Result<(), ParError> {
+        // no thread can be spawned with such a stack
+        let cfg = ParConfig::builder().threads(3).stack_size(1 << 60).build();
+        let caller = std::thread::current().id();
+        let kernel_fun = |d: &mut [u32]| {
+            assert_eq!(std::thread::current().id(), caller);
+            d.fill(1);
+        };
+        let mut dest = vec![0_u32; 30];
+        par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 1));
+        Ok(())
+    }
+    #[cfg(feature = "std")]
+    #[test]
+    fn nested_test() -> Result<(), ParError> {
+        let outer = std::thread::current().id();
+        let mut dest = vec![vec![0_u32; 100]; 4];
+        let kernel_fun = |rows: &mut [Vec<u32>]| {
+            let id = std::thread::current().id();
+            assert_ne!(id, outer);
+            for r in rows {
+                // runs serially on the worker instead of spawning threads
+                let inner = |d: &mut [u32]| {
+                    assert_eq!(std::thread::current().id(), id);
+                    d.fill(1);
+                };
+                par_in_place_map(r, 4, kernel!(inner)).unwrap();
+                // empty input runs no part on the worker either
+                let mut none: [u32; 0] = [];
+                par_map(&[], &mut none, 4, kernel!(|_: &[u32], _: &mut [u32]| {})).unwrap();
+            }
+        };
+        par_in_place_map(&mut dest, 4, kernel!(kernel_fun))?;
+        assert!(dest.iter().flatten().all(|e| *e == 1));
+        Ok(())
+    }
+    #[test]
+    fn par_scan_test() -> Result<(), ParError> {
+        let src = vec![1_u32; 10];
+        let mut dest = vec![0_u32; src.len()];
+        par_scan(
+            &src,
+            &mut dest,
+            3,
+            0,
+            kernel!(|a, b| a + b),
+            Scan::Inclusive,
+        )?;
+        assert_eq!(dest, (1..=10).collect::<Vec<_>>());
+        par_scan(
+            &src,
+            &mut dest,
+            4,
+            0,
+            kernel!(|a, b| a + b),
+            Scan::Exclusive,
+        )?;
+        assert_eq!(dest, (0..10).collect::<Vec<_>>());
+        // a longer destination is left untouched
+        let mut dest = vec![7_u32; src.len() + 5];
+        let r = par_scan(
+            &src,
+            &mut dest,
+            3,
+            0,
+            kernel!(|a, b| a + b),
+            Scan::Inclusive,
+        );
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 10, dest: 15 })
+        ));
+        assert!(dest.iter().all(|e| *e == 7));
+        Ok(())
+    }
+    #[test]
+    fn par_map_borrowed_kernel_test() -> Result<(), ParError> {
+        // non-'static data captured by reference
+        let table: Vec<u32> = (0..16).map(|i| i * i).collect();
+        let src: Vec<u32> = (0..16).collect();
+        let mut dest = vec![0_u32; src.len()];
+        let t = &table;
+        let kernel_fun = move |s: &[u32], d: &mut [u32]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = t[*s as usize];
+            }
+        };
+        par_map(&src, &mut dest, 4, kernel!(kernel_fun))?;
+        assert_eq!(dest, table);
+        Ok(())
+    }
 }
@@ -0,0 +1,265 @@
+//! Dense linear algebra on flat row-major slices.
+
+use crate::reduce::reduce_with;
+use crate::{exec, split, ParConfig, ParError};
+use std::ops::{Add, Mul, Sub};
+
+// Columns of `c` and `b`, and rows of `b`, in the tiles of `par_matmul`: a
+// tile of `b` fits in the L2 cache and is reused for every row of a band.
+const TILE_N: usize = 256;
+const TILE_K: usize = 128;
+
+//-----------------------------------------------------------------------------
+/// Matrix product `c = a × b` of the `m × k` matrix `a` and the `k × n`
+/// matrix `b` into the `m × n` matrix `c`, all stored in row-major order
+/// without padding.
+///
+/// Each thread computes a band of rows of `c`, iterating over cache-sized
+/// tiles of `b`; the inner loop runs along rows of `b` and `c` so that it can
+/// be vectorized.
+///
+/// ```rust,ignore
+/// par_matmul(&a, &b, &mut c, m, n, k, 8)?;
+/// ```
+///
+/// Returns [`ParError::LengthMismatch`] if a buffer is smaller than its
+/// matrix, with the length of the buffer as `dest` and the number of elements
+/// of the matrix as `src`.
+#[allow(clippy::too_many_arguments)]
+pub fn par_matmul<T>(
+    a: &[T],
+    b: &[T],
+    c: &mut [T],
+    m: usize,
+    n: usize,
+    k: usize,
+    num_threads: usize,
+) -> Result<(), ParError>
+where
+    T: Copy + Default + Add<Output = T> + Mul<Output = T> + Send + Sync,
+{
+    for (len, size) in [(a.len(), m * k), (b.len(), k * n), (c.len(), m * n)] {
+        if len < size {
+            return Err(ParError::LengthMismatch {
+                src: size,
+                dest: len,
+            });
+        }
+    }
+    if n == 0 {
+        return Ok(());
+    }
+    let c = &mut c[..m * n];
+    c.fill(T::default());
+    let bands: Vec<_> = split::ranges(m, num_threads)
+        .into_iter()
+        .map(|r| r.start * n..r.end * n)
+        .collect();
+    let parts = split::split_ranges(c, &bands);
+    let chunks = bands.into_iter().zip(parts).collect();
+    exec::run_chunks(num_threads, chunks, |(r, c)| {
+        let i0 = r.start / n;
+        for jt in (0..n).step_by(TILE_N) {
+            let j1 = (jt + TILE_N).min(n);
+            for pt in (0..k).step_by(TILE_K) {
+                let p1 = (pt + TILE_K).min(k);
+                for (i, c_row) in c.chunks_exact_mut(n).enumerate() {
+                    let a_row = &a[(i0 + i) * k..][..k];
+                    let c_tile = &mut c_row[jt..j1];
+                    for p in pt..p1 {
+                        let aip = a_row[p];
+                        let b_tile = &b[p * n + jt..p * n + j1];
+                        for (c, b) in c_tile.iter_mut().zip(b_tile) {
+                            *c = *c + aip * *b;
+                        }
+                    }
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Floating-point element of the vector functions such as [`par_dot`] and of
+/// [`par_sum_compensated`](crate::par_sum_compensated).
+pub trait Float:
+    Copy
+    + Default
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Send
+    + Sync
+{
+    /// Square root.
+    fn sqrt(self) -> Self;
+    /// Absolute value.
+    fn abs(self) -> Self;
+}
+
+impl Float for f32 {
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+}
+
+impl Float for f64 {
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+
+fn threads(num_threads: usize) -> ParConfig {
+    ParConfig::builder().threads(num_threads).build()
+}
+
+//-----------------------------------------------------------------------------
+/// Dot product of `x` and `y`: each thread sums the products over its
+/// sub-range and the partial sums are added in sub-range order.
+///
+/// Returns [`ParError::LengthMismatch`] if the vectors have different lengths.
+pub fn par_dot<T: Float>(x: &[T], y: &[T], num_threads: usize) -> Result<T, ParError> {
+    par_dot_with(&threads(num_threads), x, y)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_dot`] with the execution parameters taken from `cfg`, see
+/// [`ParConfigBuilder::deterministic`](crate::ParConfigBuilder::deterministic)
+/// for results independent of the number of threads.
+pub fn par_dot_with<T: Float>(cfg: &ParConfig, x: &[T], y: &[T]) -> Result<T, ParError> {
+    crate::check_len(x.len(), y.len())?;
+    reduce_with(
+        cfg,
+        (x, y),
+        T::default(),
+        |(x, y)| {
+            x.iter()
+                .zip(y)
+                .fold(T::default(), |acc, (a, b)| acc + *a * *b)
+        },
+        |a, b| a + b,
+    )
+}
+
+//-----------------------------------------------------------------------------
+/// Euclidean norm of `x`, the square root of the sum of the squares computed
+/// as in [`par_dot`]; elements beyond the square root of the largest finite
+/// value overflow to infinity.
+pub fn par_norm<T: Float>(x: &[T], num_threads: usize) -> Result<T, ParError> {
+    par_norm_with(&threads(num_threads), x)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_norm`] with the execution parameters taken from `cfg`, see
+/// [`par_dot_with`].
+pub fn par_norm_with<T: Float>(cfg: &ParConfig, x: &[T]) -> Result<T, ParError> {
+    Ok(par_dot_with(cfg, x, x)?.sqrt())
+}
+
+//-----------------------------------------------------------------------------
+/// `y += a * x` element-wise, each thread updating its own sub-range.
+///
+/// Returns [`ParError::LengthMismatch`] if the vectors have different lengths.
+pub fn par_axpy<T: Float>(a: T, x: &[T], y: &mut [T], num_threads: usize) -> Result<(), ParError> {
+    crate::check_len(x.len(), y.len())?;
+    exec::run_chunks(
+        num_threads,
+        split::chunks((x, y), num_threads),
+        |(_, (x, y))| {
+            for (y, x) in y.iter_mut().zip(x) {
+                *y = *y + a * *x;
+            }
+        },
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn matmul<T>(a: &[T], b: &[T], m: usize, n: usize, k: usize) -> Vec<T>
+    where
+        T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+    {
+        let mut c = vec![T::default(); m * n];
+        for i in 0..m {
+            for j in 0..n {
+                for p in 0..k {
+                    c[i * n + j] = c[i * n + j] + a[i * k + p] * b[p * n + j];
+                }
+            }
+        }
+        c
+    }
+    #[test]
+    fn par_matmul_test() -> Result<(), ParError> {
+        for (m, n, k) in [(37, 300, 141), (5, 3, 0), (0, 4, 4), (1, 1, 1)] {
+            let a: Vec<u64> = (0..m * k).map(|i| (i % 13) as u64).collect();
+            let b: Vec<u64> = (0..k * n).map(|i| (i % 7) as u64).collect();
+            let mut c = vec![1; m * n];
+            par_matmul(&a, &b, &mut c, m, n, k, 4)?;
+            assert_eq!(c, matmul(&a, &b, m, n, k), "{m} x {n} x {k}");
+        }
+        // exact in f64: small integers
+        let (m, n, k) = (20, 30, 40);
+        let a: Vec<f64> = (0..m * k).map(|i| (i % 5) as f64).collect();
+        let b: Vec<f64> = (0..k * n).map(|i| (i % 3) as f64 - 1.0).collect();
+        let mut c = vec![0.0; m * n];
+        par_matmul(&a, &b, &mut c, m, n, k, 3)?;
+        assert_eq!(c, matmul(&a, &b, m, n, k));
+        let r = par_matmul(&a, &b[1..], &mut c, m, n, k, 3);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: 1200,
+                dest: 1199
+            })
+        ));
+        let r = par_matmul(&a, &b, &mut c[..599], m, n, k, 3);
+        assert!(matches!(r, Err(ParError::LengthMismatch { .. })));
+        Ok(())
+    }
+    #[test]
+    fn blas1_test() -> Result<(), ParError> {
+        let x: Vec<f64> = (0..1000).map(|i| i as f64).collect();
+        let y = vec![2.0; 1000];
+        assert_eq!(par_dot(&x, &y, 4)?, 999_000.0);
+        assert_eq!(par_norm(&[3.0_f32, 4.0], 2)?, 5.0);
+        assert_eq!(par_norm::<f32>(&[], 2)?, 0.0);
+        let mut z = y.clone();
+        par_axpy(0.5, &x, &mut z, 3)?;
+        assert!(z
+            .iter()
+            .enumerate()
+            .all(|(i, z)| *z == 2.0 + i as f64 / 2.0));
+        assert!(matches!(
+            par_dot(&x, &y[1..], 4),
+            Err(ParError::LengthMismatch { .. })
+        ));
+        // inexact sums, reproducible in deterministic mode
+        let x: Vec<f32> = (0..100_000).map(|i| 1.0 / (1 + i % 977) as f32).collect();
+        let dot = |n, deterministic| {
+            let cfg = ParConfig::builder()
+                .threads(n)
+                .deterministic(deterministic)
+                .build();
+            par_dot_with(&cfg, &x, &x)
+        };
+        let expected = dot(1, true)?;
+        for n in [2, 3, 7] {
+            assert_eq!(dot(n, true)?.to_bits(), expected.to_bits());
+        }
+        assert!((dot(3, false)? - expected).abs() < expected * 1e-3);
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Reduction operators for [`par_reduce_monoid`](crate::par_reduce_monoid).
+//!
+//! ```rust,ignore
+//! use par_seq::monoid::{Max, Sum};
+//! let total = par_reduce_monoid::<Sum<u64>>(&data, 8)?;
+//! let largest = par_reduce_monoid::<Max<f32>>(&samples, 8)?;
+//! ```
+
+use core::marker::PhantomData;
+
+//-----------------------------------------------------------------------------
+/// Associative operation with an identity element.
+///
+/// `combine` is only required to be associative: partial results are always
+/// combined in sequence order, so that non-commutative operations such as
+/// concatenation are supported.
+pub trait Monoid {
+    /// Type of the elements and of the result.
+    type Item;
+    /// Element `e` such that `combine(e, x) == combine(x, e) == x`.
+    fn identity() -> Self::Item;
+    /// Combine `a` with `b`, `a` coming first in the sequence.
+    fn combine(a: Self::Item, b: Self::Item) -> Self::Item;
+}
+
+macro_rules! operator {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        pub struct $name<T>(PhantomData<T>);
+    };
+}
+
+operator!(
+    /// Sum, identity `0`.
+    Sum
+);
+operator!(
+    /// Product, identity `1`.
+    Product
+);
+operator!(
+    /// Minimum, identity the largest value; for floats `f32::min` semantics,
+    /// NaNs are ignored.
+    Min
+);
+operator!(
+    /// Maximum, identity the smallest value; for floats `f32::max`
+    /// semantics, NaNs are ignored.
+    Max
+);
+operator!(
+    /// Bitwise and, identity all bits set.
+    BitAnd
+);
+operator!(
+    /// Bitwise or, identity `0`.
+    BitOr
+);
+operator!(
+    /// Bitwise exclusive or, identity `0`.
+    BitXor
+);
+
+macro_rules! monoid {
+    ($op:ident, $t:ty, $identity:expr, |$a:ident, $b:ident| $combine:expr) => {
+        impl Monoid for $op<$t> {
+            type Item = $t;
+            fn identity() -> $t {
+                $identity
+            }
+            fn combine($a: $t, $b: $t) -> $t {
+                $combine
+            }
+        }
+    };
+}
+
+macro_rules! integer_monoids {
+    ($($t:ty),+) => {$(
+        monoid!(Sum, $t, 0, |a, b| a + b);
+        monoid!(Product, $t, 1, |a, b| a * b);
+        monoid!(Min, $t, <$t>::MAX, |a, b| a.min(b));
+        monoid!(Max, $t, <$t>::MIN, |a, b| a.max(b));
+        monoid!(BitAnd, $t, !0, |a, b| a & b);
+        monoid!(BitOr, $t, 0, |a, b| a | b);
+        monoid!(BitXor, $t, 0, |a, b| a ^ b);
+    )+};
+}
+
+integer_monoids!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+macro_rules! float_monoids {
+    ($($t:ty),+) => {$(
+        monoid!(Sum, $t, 0.0, |a, b| a + b);
+        monoid!(Product, $t, 1.0, |a, b| a * b);
+        monoid!(Min, $t, <$t>::INFINITY, |a, b| a.min(b));
+        monoid!(Max, $t, <$t>::NEG_INFINITY, |a, b| a.max(b));
+    )+};
+}
+
+float_monoids!(f32, f64);
+
+monoid!(BitAnd, bool, true, |a, b| a & b);
+monoid!(BitOr, bool, false, |a, b| a | b);
+monoid!(BitXor, bool, false, |a, b| a ^ b);
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn fold<M: Monoid>(items: impl IntoIterator<Item = M::Item>) -> M::Item {
+        items.into_iter().fold(M::identity(), M::combine)
+    }
+    #[test]
+    fn monoid_test() {
+        assert_eq!(fold::<Sum<u32>>([1, 2, 3]), 6);
+        assert_eq!(fold::<Product<i64>>([-2, 3]), -6);
+        assert_eq!(fold::<Min<u8>>([]), u8::MAX);
+        assert_eq!(fold::<Max<f64>>([1.0, f64::NAN, 3.0]), 3.0);
+        assert_eq!(fold::<BitAnd<u8>>([0b1110, 0b0111]), 0b0110);
+        assert_eq!(fold::<BitOr<u16>>([1, 4]), 5);
+        assert!(fold::<BitXor<bool>>([true, true, true]));
+    }
+}
@@ -0,0 +1,174 @@
+//! Multi-stage pipelines.
+
+use crate::{exec, split, Kernel, KernelFun2, ParError};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+// Default number of bytes of a block, small enough for the two buffers of
+// every worker to stay in the cache.
+const BLOCK_BYTES: usize = 64 << 10;
+
+//-----------------------------------------------------------------------------
+/// Ordered list of kernels applied to a sequence in a single parallel pass.
+///
+/// The sequence is split into blocks and each worker runs all the stages on
+/// a block before moving to the next one, ping-ponging the intermediate
+/// results between two per-worker buffers: there is no barrier between the
+/// stages and the sequence is read and written only once, while the workers
+/// run different stages on different blocks at the same time.
+///
+/// Every stage is a copy kernel `Fn(&[T], &mut [T])` receiving source and
+/// destination blocks of the same length.
+///
+/// ```rust,ignore
+/// let pipeline = Pipeline::new()
+///     .stage(kernel!(denoise))
+///     .stage(kernel!(sharpen))
+///     .stage(kernel!(gamma));
+/// pipeline.run(&src, &mut dest, 8)?;
+/// ```
+pub struct Pipeline<'a, T> {
+    stages: Vec<Arc<KernelFun2<'a, T>>>,
+    block_size: Option<usize>,
+}
+
+impl<T> Default for Pipeline<'_, T> {
+    fn default() -> Self {
+        Pipeline {
+            stages: Vec::new(),
+            block_size: None,
+        }
+    }
+}
+
+impl<'a, T: Clone + Send + Sync> Pipeline<'a, T> {
+    /// Create a pipeline without stages, which copies the source.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Append a stage.
+    pub fn stage<K: Kernel<T> + ?Sized + 'a>(mut self, fr: Arc<K>) -> Self {
+        self.stages
+            .push(Arc::new(move |s: &[T], d: &mut [T]| fr.run(s, d)));
+        self
+    }
+    /// Process blocks of `block_size` elements instead of the default of
+    /// 64 KiB.
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = Some(block_size.max(1));
+        self
+    }
+    /// Number of stages.
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+    /// Return `true` if there are no stages.
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+    /// Run all the stages on `src`, writing the output of the last one into
+    /// `dest`.
+    pub fn run(&self, src: &[T], dest: &mut [T], num_threads: usize) -> Result<(), ParError> {
+        crate::check_len(src.len(), dest.len())?;
+        let data = (src, dest);
+        let ranges = split::fixed_ranges(split::Split::len(&data), self.block());
+        exec::run_stateful(
+            num_threads,
+            split::zip_ranges(data, ranges),
+            |_| (Vec::new(), Vec::new()),
+            |(a, b), (_, (s, d))| self.run_block(s, d, a, b),
+        )?;
+        Ok(())
+    }
+    /// Same as [`Pipeline::run`] with `dest` as the source.
+    pub fn run_in_place(&self, dest: &mut [T], num_threads: usize) -> Result<(), ParError> {
+        let ranges = split::fixed_ranges(dest.len(), self.block());
+        exec::run_stateful(
+            num_threads,
+            split::zip_ranges(dest, ranges),
+            |_| (Vec::new(), Vec::new(), Vec::new()),
+            |(input, a, b), (_, d)| {
+                input.clear();
+                input.extend_from_slice(d);
+                self.run_block(input, d, a, b)
+            },
+        )?;
+        Ok(())
+    }
+
+    fn block(&self) -> usize {
+        self.block_size
+            .unwrap_or(BLOCK_BYTES / core::mem::size_of::<T>().max(1))
+            .max(1)
+    }
+
+    // Run the stages on a block, `a` and `b` are the intermediate buffers.
+    fn run_block(&self, s: &[T], d: &mut [T], a: &mut Vec<T>, b: &mut Vec<T>) {
+        let Some((last, rest)) = self.stages.split_last() else {
+            d.clone_from_slice(s);
+            return;
+        };
+        let Some((first, middle)) = rest.split_first() else {
+            return last(s, d);
+        };
+        for buf in [&mut *a, &mut *b] {
+            buf.clear();
+            buf.extend_from_slice(s);
+        }
+        first(s, a);
+        for stage in middle {
+            stage(a, b);
+            core::mem::swap(a, b);
+        }
+        last(a, d)
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    use alloc::vec;
+    #[test]
+    fn pipeline_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..10_000).collect();
+        let mut dest = vec![0_u32; src.len()];
+        let add = |x: u32| {
+            kernel!(move |s: &[u32], d: &mut [u32]| {
+                for (d, s) in d.iter_mut().zip(s) {
+                    *d = s + x;
+                }
+            })
+        };
+        let double = kernel!(|s: &[u32], d: &mut [u32]| {
+            for (d, s) in d.iter_mut().zip(s) {
+                *d = s * 2;
+            }
+        });
+        Pipeline::new().run(&src, &mut dest, 3)?;
+        assert_eq!(dest, src);
+        let pipeline = Pipeline::new().stage(add(1)).block_size(100);
+        pipeline.run(&src, &mut dest, 3)?;
+        assert!(dest.iter().enumerate().all(|(i, e)| *e == i as u32 + 1));
+        let pipeline = Pipeline::new()
+            .stage(add(1))
+            .stage(double)
+            .stage(add(3))
+            .stage(add(5))
+            .block_size(333);
+        assert_eq!(pipeline.len(), 4);
+        pipeline.run(&src, &mut dest, 4)?;
+        assert!(dest
+            .iter()
+            .enumerate()
+            .all(|(i, e)| *e == (i as u32 + 1) * 2 + 8));
+        pipeline.run_in_place(&mut dest, 4)?;
+        assert!(dest
+            .iter()
+            .enumerate()
+            .all(|(i, e)| *e == ((i as u32 + 1) * 2 + 8 + 1) * 2 + 8));
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+//! Commonly used items: `use par_seq::prelude::*;`.
+
+pub use crate::kernel;
+#[cfg(feature = "std")]
+pub use crate::ParSliceExt;
+pub use crate::{ParConfig, ParError, Scheduling};
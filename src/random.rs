@@ -0,0 +1,320 @@
+//! Random permutations, sampling and random numbers.
+
+use crate::{exec, split, ParError};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+// Increment of the SplitMix64 state.
+const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// Number of elements drawn from each stream by `par_random_fill`, fixed so
+// that the output does not depend on the number of threads.
+const FILL_BLOCK: usize = 1 << 12;
+
+type DistFun<'a, T> = dyn Fn(&mut Rng) -> T + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// SplitMix64 pseudo-random generator, passed to the distribution of
+/// [`par_random_fill`].
+///
+/// Not cryptographically secure; generators derived from the same seed with
+/// different stream indices produce independent sequences.
+pub struct Rng(u64);
+
+impl Rng {
+    /// Generator seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+    /// Stream `index` of the generators derived from `seed`.
+    pub fn stream(seed: u64, index: u64) -> Self {
+        Self(Self(seed ^ index.wrapping_mul(GAMMA).rotate_left(32)).next_u64())
+    }
+    /// Next uniformly distributed 64-bit integer.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(GAMMA);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// Uniform integer in `0..n`, without modulo bias.
+    ///
+    /// Panics if `n` is `0`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "empty range");
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+    /// Uniform float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1_u64 << 53) as f64)
+    }
+}
+
+// Fisher-Yates shuffle.
+fn shuffle<T>(s: &mut [T], rng: &mut Rng) {
+    for i in (1..s.len()).rev() {
+        s.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+}
+
+// Uniform merge of the shuffled `s[..mid]` and `s[mid..]` (MergeShuffle,
+// Bacher et al.): take the next element from either half with probability
+// 1/2 until one is exhausted, then insert the rest of the other one at random
+// positions.
+fn merge<T>(s: &mut [T], mid: usize, rng: &mut Rng) {
+    let (mut i, mut j) = (0, mid);
+    let mut bits = 0;
+    let mut left = 0;
+    loop {
+        if left == 0 {
+            bits = rng.next_u64();
+            left = 64;
+        }
+        let take_right = bits & 1 == 1;
+        bits >>= 1;
+        left -= 1;
+        if take_right {
+            if j == s.len() {
+                break;
+            }
+            s.swap(i, j);
+            j += 1;
+        } else if i == j {
+            break;
+        }
+        i += 1;
+    }
+    for i in i..s.len() {
+        s.swap(i, rng.below(i as u64 + 1) as usize);
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Shuffle `dest` in place, every permutation being equally likely.
+///
+/// Each thread shuffles a sub-range, then adjacent sub-ranges are merged in
+/// parallel two by two with random interleavings until one is left
+/// (MergeShuffle). Every sub-range and merge uses its own generator derived
+/// from `seed`: the result is reproducible for a given seed, length and
+/// number of threads, but not across numbers of threads.
+///
+/// ```rust,ignore
+/// par_shuffle(&mut rows, 8, 42)?;
+/// ```
+pub fn par_shuffle<T: Send>(dest: &mut [T], num_threads: usize, seed: u64) -> Result<(), ParError> {
+    let mut ranges = split::ranges(dest.len(), num_threads);
+    let mut stream = 0;
+    let jobs: Vec<_> = split::split_ranges(&mut *dest, &ranges)
+        .into_iter()
+        .map(|s| {
+            stream += 1;
+            (s, Rng::stream(seed, stream))
+        })
+        .collect();
+    exec::run(num_threads, jobs, |(s, mut rng)| shuffle(s, &mut rng))?;
+    while ranges.len() > 1 {
+        // pairs of adjacent ranges, the last one alone if odd
+        let merged: Vec<Range<usize>> = ranges
+            .chunks(2)
+            .map(|p| p[0].start..p[p.len() - 1].end)
+            .collect();
+        let jobs: Vec<_> = split::split_ranges(&mut *dest, &merged)
+            .into_iter()
+            .zip(ranges.chunks(2))
+            .filter(|(_, p)| p.len() == 2)
+            .map(|(s, p)| {
+                stream += 1;
+                (s, p[1].start - p[0].start, Rng::stream(seed, stream))
+            })
+            .collect();
+        exec::run(num_threads, jobs, |(s, mid, mut rng)| {
+            merge(s, mid, &mut rng)
+        })?;
+        ranges = merged;
+    }
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Fill `dest` with values drawn from `dist`, which receives the generator.
+///
+/// The sequence is split into blocks of a fixed size, each drawn from its own
+/// stream derived from `seed`: the result is reproducible for a given seed
+/// whatever the number of threads.
+///
+/// ```rust,ignore
+/// // uniform in [-1, 1)
+/// par_random_fill(&mut weights, 8, 42, kernel!(|r: &mut Rng| r.next_f64() * 2.0 - 1.0))?;
+/// ```
+pub fn par_random_fill<T: Send>(
+    dest: &mut [T],
+    num_threads: usize,
+    seed: u64,
+    dist: Arc<DistFun<T>>,
+) -> Result<(), ParError> {
+    let ranges = split::fixed_ranges(dest.len(), FILL_BLOCK);
+    exec::run_chunks(num_threads, split::zip_ranges(dest, ranges), |(r, d)| {
+        let mut rng = Rng::stream(seed, (r.start / FILL_BLOCK) as u64);
+        d.iter_mut().for_each(|e| *e = dist(&mut rng));
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Draw `k` distinct elements of `src` uniformly at random, or all of them if
+/// `k >= src.len()`, in no particular order.
+///
+/// Each thread draws a reservoir of up to `k` elements from its sub-range;
+/// the reservoirs are then merged by drawing the elements one at a time from
+/// a sub-range chosen with a probability proportional to its number of
+/// elements not yet drawn. The result is reproducible for a given seed,
+/// length and number of threads.
+///
+/// ```rust,ignore
+/// let sample = par_sample(&records, 1000, 8, 42)?;
+/// ```
+pub fn par_sample<T: Clone + Sync + Send>(
+    src: &[T],
+    k: usize,
+    num_threads: usize,
+    seed: u64,
+) -> Result<Vec<T>, ParError> {
+    let chunks = split::chunks(src, num_threads);
+    let mut reservoirs = exec::run_chunks(num_threads, chunks, |(r, s)| {
+        // algorithm R
+        let mut rng = Rng::stream(seed, r.start as u64 + 1);
+        let mut reservoir: Vec<&T> = s.iter().take(k).collect();
+        for (i, e) in s.iter().enumerate().skip(k) {
+            let j = rng.below(i as u64 + 1) as usize;
+            if j < k {
+                reservoir[j] = e;
+            }
+        }
+        (s.len(), reservoir)
+    })?;
+    let mut rng = Rng::stream(seed, 0);
+    let mut left = src.len();
+    let mut sample = Vec::with_capacity(k.min(left));
+    while sample.len() < k && left > 0 {
+        let mut x = rng.below(left as u64) as usize;
+        let (n, reservoir) = reservoirs
+            .iter_mut()
+            .find(|(n, _)| match x.checked_sub(*n) {
+                Some(rest) => {
+                    x = rest;
+                    false
+                }
+                None => true,
+            })
+            .expect("x < left");
+        let j = rng.below(reservoir.len() as u64) as usize;
+        sample.push(reservoir.swap_remove(j).clone());
+        *n -= 1;
+        left -= 1;
+    }
+    Ok(sample)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    #[test]
+    fn par_shuffle_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..10_000).collect();
+        for n in [1, 3, 4, 7] {
+            let mut a = src.clone();
+            par_shuffle(&mut a, n, 42)?;
+            let mut b = src.clone();
+            par_shuffle(&mut b, n, 42)?;
+            assert_eq!(a, b);
+            assert_ne!(a, src);
+            let mut sorted = a.clone();
+            sorted.sort();
+            assert_eq!(sorted, src);
+            par_shuffle(&mut b, n, 43)?;
+            assert_ne!(a, b);
+        }
+        // every permutation of 3 elements, across the merge of 3 sub-ranges
+        let mut counts = [0; 6];
+        for seed in 0..3000 {
+            let mut p = [0, 1, 2];
+            par_shuffle(&mut p, 3, seed)?;
+            let i = [
+                [0, 1, 2],
+                [0, 2, 1],
+                [1, 0, 2],
+                [1, 2, 0],
+                [2, 0, 1],
+                [2, 1, 0],
+            ]
+            .iter()
+            .position(|q| *q == p)
+            .unwrap();
+            counts[i] += 1;
+        }
+        assert!(counts.iter().all(|c| (400..600).contains(c)), "{counts:?}");
+        par_shuffle::<u8>(&mut [], 4, 0)?;
+        let mut one = vec![1];
+        par_shuffle(&mut one, 4, 0)?;
+        assert_eq!(one, [1]);
+        Ok(())
+    }
+    #[test]
+    fn par_sample_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..10_000).collect();
+        let mut sample = par_sample(&src, 100, 4, 3)?;
+        assert_eq!(sample, par_sample(&src, 100, 4, 3)?);
+        sample.sort();
+        sample.dedup();
+        assert_eq!(sample.len(), 100);
+        let mut all = par_sample(&src[..50], 100, 4, 3)?;
+        all.sort();
+        assert_eq!(all, &src[..50]);
+        assert!(par_sample(&src, 0, 4, 3)?.is_empty());
+        // every element drawn with probability k / len, across uneven
+        // sub-ranges
+        let mut counts = [0; 10];
+        for seed in 0..2000 {
+            for e in par_sample(&src[..10], 3, 3, seed)? {
+                counts[e as usize] += 1;
+            }
+        }
+        assert!(counts.iter().all(|c| (520..680).contains(c)), "{counts:?}");
+        Ok(())
+    }
+    #[test]
+    fn par_random_fill_test() -> Result<(), ParError> {
+        let uniform = crate::kernel!(|r: &mut Rng| r.next_f64());
+        let mut expected = vec![0.0; 100_000];
+        par_random_fill(&mut expected, 1, 7, uniform.clone())?;
+        for n in [2, 5, 8] {
+            let mut d = vec![0.0; 100_000];
+            par_random_fill(&mut d, n, 7, uniform.clone())?;
+            assert_eq!(d, expected);
+        }
+        assert!(expected.iter().all(|x| (0.0..1.0).contains(x)));
+        let mean = expected.iter().sum::<f64>() / expected.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01);
+        let mut d = vec![0.0; 100_000];
+        par_random_fill(&mut d, 4, 8, uniform)?;
+        assert_ne!(d, expected);
+        let mut dice = vec![0_u64; 60_000];
+        par_random_fill(&mut dice, 4, 1, crate::kernel!(|r: &mut Rng| r.below(6)))?;
+        for face in 0..6 {
+            let c = dice.iter().filter(|d| **d == face).count();
+            assert!((9_500..10_500).contains(&c));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,682 @@
+//! Reductions, combining the partial results in sequence order.
+
+use crate::split::{self, Split};
+use crate::{
+    exec, ChunkFun, CombineFun, FactoryFun, Float, FoldFun, Monoid, ParConfig, ParError, PredFun,
+};
+use std::sync::Arc;
+
+type BinFun<'a, T> = dyn Fn(&T) -> usize + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+// Reduce each sub-range with `chunk_fn` on the worker threads, then fold the
+// partial results into `identity` with `combine`, in sub-range order.
+pub(crate) fn reduce<T: Sync, R: Send>(
+    src: &[T],
+    num_threads: usize,
+    identity: R,
+    chunk_fn: impl Fn(&[T]) -> R + Sync,
+    combine: impl Fn(R, R) -> R,
+) -> Result<R, ParError> {
+    let partials = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| {
+        chunk_fn(s)
+    })?;
+    Ok(partials.into_iter().fold(identity, combine))
+}
+
+// Number of elements per sub-range of the deterministic reductions, fixed so
+// that the partial results do not depend on the number of threads.
+const DETERMINISTIC_CHUNK: usize = 1 << 14;
+
+// Same as `reduce` over the sub-ranges of `data` selected by `cfg`; in
+// deterministic mode, over sub-ranges of `DETERMINISTIC_CHUNK` elements whose
+// results are combined pairwise, see `pairwise`. The sub-ranges are skipped
+// once the call is cancelled or times out.
+pub(crate) fn reduce_with<P: Split, R: Send>(
+    cfg: &ParConfig,
+    data: P,
+    identity: R,
+    chunk_fn: impl Fn(P) -> R + Sync,
+    combine: impl Fn(R, R) -> R,
+) -> Result<R, ParError> {
+    let deadline = cfg.deadline();
+    let chunk_fn = |(_, p)| (!cfg.is_stopped(deadline)).then(|| chunk_fn(p));
+    let partials = if cfg.deterministic() {
+        exec::run_with(&cfg.fixed_chunks(DETERMINISTIC_CHUNK), data, chunk_fn)?
+    } else {
+        exec::run_with(cfg, data, chunk_fn)?
+    };
+    cfg.check_stopped(deadline)?;
+    // not stopped: no sub-range was skipped
+    let partials = partials.into_iter().flatten();
+    if cfg.deterministic() {
+        return Ok(match pairwise(partials.collect(), &combine) {
+            Some(r) => combine(identity, r),
+            None => identity,
+        });
+    }
+    Ok(partials.fold(identity, combine))
+}
+
+// Combine adjacent partial results two by two until one is left: the shape of
+// the tree only depends on their number, and for sums the rounding error
+// grows with its depth, logarithmic in the length, instead of linearly.
+fn pairwise<R>(mut partials: Vec<R>, combine: impl Fn(R, R) -> R) -> Option<R> {
+    while partials.len() > 1 {
+        let mut next = Vec::with_capacity(partials.len().div_ceil(2));
+        let mut it = partials.into_iter();
+        while let Some(a) = it.next() {
+            next.push(match it.next() {
+                Some(b) => combine(a, b),
+                None => a,
+            });
+        }
+        partials = next;
+    }
+    partials.pop()
+}
+
+//-----------------------------------------------------------------------------
+/// Reduce each sub-range with `chunk_fn` on its own thread, then fold the
+/// partial results into `identity` with `combine`, in sub-range order.
+pub fn par_reduce<T: Sync, R: Send>(
+    src: &[T],
+    num_threads: usize,
+    identity: R,
+    chunk_fn: Arc<ChunkFun<T, R>>,
+    combine: Arc<CombineFun<R>>,
+) -> Result<R, ParError> {
+    reduce(
+        src,
+        num_threads,
+        identity,
+        |s| chunk_fn(s),
+        |a, b| combine(a, b),
+    )
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_reduce`] with the execution parameters taken from `cfg`; see
+/// [`ParConfigBuilder::deterministic`](crate::ParConfigBuilder::deterministic)
+/// for results independent of the number of threads, `combine` then being
+/// required to be associative only.
+pub fn par_reduce_with<T: Sync, R: Send>(
+    cfg: &ParConfig,
+    src: &[T],
+    identity: R,
+    chunk_fn: Arc<ChunkFun<T, R>>,
+    combine: Arc<CombineFun<R>>,
+) -> Result<R, ParError> {
+    reduce_with(cfg, src, identity, |s| chunk_fn(s), |a, b| combine(a, b))
+}
+
+//-----------------------------------------------------------------------------
+/// Reduce `src` with the operator `M`, see [`monoid`](crate::monoid): each
+/// thread folds its sub-range starting from the identity and the partial
+/// results are combined in sub-range order.
+///
+/// ```rust,ignore
+/// let total = par_reduce_monoid::<Sum<u64>>(&data, 8)?;
+/// ```
+pub fn par_reduce_monoid<M: Monoid>(
+    src: &[M::Item],
+    num_threads: usize,
+) -> Result<M::Item, ParError>
+where
+    M::Item: Clone + Send + Sync,
+{
+    reduce(
+        src,
+        num_threads,
+        M::identity(),
+        |s| s.iter().cloned().fold(M::identity(), M::combine),
+        M::combine,
+    )
+}
+
+//-----------------------------------------------------------------------------
+/// Map each sub-range to a partial result with `map` on its own thread and
+/// combine the partial results in sub-range order with `reduce`, in a single
+/// pass over `src` and without an intermediate buffer; `None` for an empty
+/// sequence.
+///
+/// Same as [`par_reduce`] for results without an identity value.
+///
+/// ```rust,ignore
+/// let longest = par_map_reduce(
+///     &lines,
+///     8,
+///     kernel!(|s: &[String]| s.iter().map(|l| l.len()).max().unwrap_or(0)),
+///     kernel!(|a: usize, b| a.max(b)),
+/// )?;
+/// ```
+pub fn par_map_reduce<T: Sync, R: Send>(
+    src: &[T],
+    num_threads: usize,
+    map: Arc<ChunkFun<T, R>>,
+    reduce: Arc<CombineFun<R>>,
+) -> Result<Option<R>, ParError> {
+    let partials = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| {
+        map(s)
+    })?;
+    Ok(partials.into_iter().reduce(|a, b| reduce(a, b)))
+}
+
+//-----------------------------------------------------------------------------
+/// Map each sub-range to a value with `fr` on its own thread and return the
+/// values in sub-range order, one per sub-range; empty for an empty sequence.
+///
+/// ```rust,ignore
+/// let blocks = par_map_chunks(&samples, 8, kernel!(|s: &[f32]| compress(s)))?;
+/// ```
+pub fn par_map_chunks<T: Sync, R: Send>(
+    src: &[T],
+    num_threads: usize,
+    fr: Arc<ChunkFun<T, R>>,
+) -> Result<Vec<R>, ParError> {
+    let results = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| fr(s))?;
+    Ok(results)
+}
+
+//-----------------------------------------------------------------------------
+/// Fold each sub-range element-wise into a per-thread accumulator created by
+/// `init`, then merge the accumulators in sub-range order with `merge`.
+pub fn par_fold<T: Sync, A: Send>(
+    src: &[T],
+    num_threads: usize,
+    init: Arc<FactoryFun<A>>,
+    fold: Arc<FoldFun<A, T>>,
+    merge: Arc<CombineFun<A>>,
+) -> Result<A, ParError> {
+    reduce(
+        src,
+        num_threads,
+        init(),
+        |s| s.iter().fold(init(), |a, e| fold(a, e)),
+        |a, b| merge(a, b),
+    )
+}
+
+//-----------------------------------------------------------------------------
+/// Count the elements matching `pred`.
+///
+/// Each thread counts its own sub-range; the total never exceeds the length
+/// of the sequence, so the sum of the counts cannot overflow.
+pub fn par_count<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<usize, ParError> {
+    reduce(
+        src,
+        num_threads,
+        0_usize,
+        |s| s.iter().filter(|e| pred(e)).count(),
+        |a, b| a + b,
+    )
+}
+
+//-----------------------------------------------------------------------------
+/// Count the elements falling in each of `num_bins` bins, as selected by
+/// `bin_fn`; bin indices past the last bin are ignored.
+///
+/// Each thread fills a private histogram, the histograms are then summed.
+pub fn par_histogram<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    num_bins: usize,
+    bin_fn: Arc<BinFun<T>>,
+) -> Result<Vec<u64>, ParError> {
+    reduce(
+        src,
+        num_threads,
+        vec![0; num_bins],
+        |s| {
+            let mut h = vec![0_u64; num_bins];
+            for e in s {
+                if let Some(b) = h.get_mut(bin_fn(e)) {
+                    *b += 1;
+                }
+            }
+            h
+        },
+        |mut a, b| {
+            a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+            a
+        },
+    )
+}
+
+//-----------------------------------------------------------------------------
+// Numeric reductions, `None` for empty sequences
+//-----------------------------------------------------------------------------
+/// Sum of the elements.
+pub fn par_sum<T>(src: &[T], num_threads: usize) -> Result<Option<T>, ParError>
+where
+    T: Copy + Send + Sync + std::iter::Sum<T>,
+{
+    reduce(
+        src,
+        num_threads,
+        None,
+        |s| Some(s.iter().copied().sum()),
+        |a, b| merge(a, b, |a, b| [a, b].into_iter().sum()),
+    )
+}
+
+/// Same as [`par_sum`] with the execution parameters taken from `cfg`, see
+/// [`par_reduce_with`]; in deterministic mode, floating-point sums are
+/// reproducible bit for bit and the partial sums are added pairwise.
+pub fn par_sum_with<T>(cfg: &ParConfig, src: &[T]) -> Result<Option<T>, ParError>
+where
+    T: Copy + Send + Sync + std::iter::Sum<T>,
+{
+    reduce_with(
+        cfg,
+        src,
+        None,
+        |s: &[T]| Some(s.iter().copied().sum()),
+        |a, b| merge(a, b, |a, b| [a, b].into_iter().sum()),
+    )
+}
+
+/// Sum of the elements computed with Neumaier's variant of Kahan summation,
+/// `0` for an empty sequence.
+///
+/// Each thread accumulates its sub-range along with the rounding error of
+/// every addition, and the partial sums are combined in sub-range order the
+/// same way, their errors being added; the error of the result hardly grows
+/// with the length, at the cost of a few more operations per element.
+///
+/// ```rust,ignore
+/// let total = par_sum_compensated(&samples, 8)?;
+/// ```
+pub fn par_sum_compensated<T: Float>(src: &[T], num_threads: usize) -> Result<T, ParError> {
+    let (sum, error) = reduce(
+        src,
+        num_threads,
+        (T::default(), T::default()),
+        |s| {
+            s.chunks(COMPENSATED_BLOCK)
+                .fold((T::default(), T::default()), |(sum, error), b| {
+                    let (sum, error) = b
+                        .iter()
+                        .fold((sum, error), |(sum, error), e| neumaier(sum, error, *e));
+                    neumaier(sum, T::default(), error)
+                })
+        },
+        |(sum, error), (s, e)| {
+            let (sum, error) = neumaier(sum, error, s);
+            (sum, error + e)
+        },
+    )?;
+    Ok(sum + error)
+}
+
+// Number of elements after which the accumulated error is folded into the
+// sum, so that it stays small enough to be accumulated accurately itself.
+const COMPENSATED_BLOCK: usize = 1024;
+
+// Add `e` to `sum`, accumulating the rounding error into `error`.
+fn neumaier<T: Float>(sum: T, error: T, e: T) -> (T, T) {
+    let t = sum + e;
+    let lost = if sum.abs() >= e.abs() {
+        (sum - t) + e
+    } else {
+        (e - t) + sum
+    };
+    (t, error + lost)
+}
+
+/// Smallest element; comparisons with unordered values (e.g. `NaN`) keep the
+/// current minimum.
+pub fn par_min<T>(src: &[T], num_threads: usize) -> Result<Option<T>, ParError>
+where
+    T: Copy + Send + Sync + PartialOrd,
+{
+    reduce(
+        src,
+        num_threads,
+        None,
+        |s| fold_opt(s, min),
+        |a, b| merge(a, b, min),
+    )
+}
+
+/// Largest element; comparisons with unordered values (e.g. `NaN`) keep the
+/// current maximum.
+pub fn par_max<T>(src: &[T], num_threads: usize) -> Result<Option<T>, ParError>
+where
+    T: Copy + Send + Sync + PartialOrd,
+{
+    reduce(
+        src,
+        num_threads,
+        None,
+        |s| fold_opt(s, max),
+        |a, b| merge(a, b, max),
+    )
+}
+
+/// Smallest and largest elements, computed in a single pass.
+pub fn par_minmax<T>(src: &[T], num_threads: usize) -> Result<Option<(T, T)>, ParError>
+where
+    T: Copy + Send + Sync + PartialOrd,
+{
+    let minmax = |a: (T, T), b: (T, T)| (min(a.0, b.0), max(a.1, b.1));
+    reduce(
+        src,
+        num_threads,
+        None,
+        |s| s.iter().map(|e| (*e, *e)).reduce(minmax),
+        |a, b| merge(a, b, minmax),
+    )
+}
+
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+fn fold_opt<T: Copy>(s: &[T], f: impl Fn(T, T) -> T) -> Option<T> {
+    s.iter().copied().reduce(f)
+}
+
+// Combine two optional partial results.
+fn merge<T>(a: Option<T>, b: Option<T>, f: impl Fn(T, T) -> T) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(f(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_reduce_test() -> Result<(), ParError> {
+        let src: Vec<u64> = (1..=100).collect();
+        let sum = par_reduce(
+            &src,
+            3,
+            0,
+            kernel!(|s: &[u64]| s.iter().sum()),
+            kernel!(|a, b| a + b),
+        )?;
+        assert_eq!(sum, 5050);
+        let max = par_reduce(
+            &src,
+            4,
+            0,
+            kernel!(|s: &[u64]| *s.iter().max().unwrap()),
+            kernel!(|a: u64, b| a.max(b)),
+        )?;
+        assert_eq!(max, 100);
+        // partial results are combined in order
+        let digits: Vec<u8> = (0..10).collect();
+        let s = par_reduce(
+            &digits,
+            3,
+            String::new(),
+            kernel!(|s: &[u8]| s.iter().map(|d| d.to_string()).collect::<String>()),
+            kernel!(|a: String, b: String| a + &b),
+        )?;
+        assert_eq!(s, "0123456789");
+        Ok(())
+    }
+    #[test]
+    fn par_map_reduce_test() -> Result<(), ParError> {
+        let words: Vec<String> = (0..100).map(|i| "x".repeat(i % 17)).collect();
+        let longest = par_map_reduce(
+            &words,
+            4,
+            kernel!(|s: &[String]| s.iter().map(|w| w.len()).max().unwrap_or(0)),
+            kernel!(|a: usize, b| a.max(b)),
+        )?;
+        assert_eq!(longest, Some(16));
+        let digits: Vec<u8> = (0..10).collect();
+        let s = par_map_reduce(
+            &digits,
+            3,
+            kernel!(|s: &[u8]| s.iter().map(|d| d.to_string()).collect::<String>()),
+            kernel!(|a: String, b: String| a + &b),
+        )?;
+        assert_eq!(s.as_deref(), Some("0123456789"));
+        let empty: [u8; 0] = [];
+        let none = par_map_reduce(
+            &empty,
+            3,
+            kernel!(|s: &[u8]| s.len()),
+            kernel!(|a, b| a + b),
+        )?;
+        assert_eq!(none, None);
+        Ok(())
+    }
+    #[test]
+    fn par_map_chunks_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..100).collect();
+        let heads = par_map_chunks(&src, 4, kernel!(|s: &[u32]| (s[0], s.len())))?;
+        assert_eq!(heads, [(0, 25), (25, 25), (50, 25), (75, 25)]);
+        // later sub-ranges complete first
+        let heads = par_map_chunks(
+            &src,
+            4,
+            kernel!(|s: &[u32]| {
+                std::thread::sleep(std::time::Duration::from_millis(10 - s[0] as u64 / 10));
+                s[0]
+            }),
+        )?;
+        assert_eq!(heads, [0, 25, 50, 75]);
+        let lens = par_map_chunks(&src[..3], 8, kernel!(|s: &[u32]| s.len()))?;
+        assert_eq!(lens, [1, 1, 1]);
+        assert!(par_map_chunks(&src[..0], 4, kernel!(|s: &[u32]| s.len()))?.is_empty());
+        Ok(())
+    }
+    #[test]
+    fn par_reduce_monoid_test() -> Result<(), ParError> {
+        use crate::monoid::{BitOr, Max, Sum};
+        let src: Vec<u64> = (1..=1000).collect();
+        assert_eq!(par_reduce_monoid::<Sum<u64>>(&src, 4)?, 500_500);
+        assert_eq!(par_reduce_monoid::<Max<u64>>(&src, 3)?, 1000);
+        assert_eq!(par_reduce_monoid::<BitOr<u64>>(&src, 3)?, 1023);
+        assert_eq!(par_reduce_monoid::<Sum<f32>>(&[], 3)?, 0.0);
+        Ok(())
+    }
+    #[test]
+    fn ordered_reduction_test() -> Result<(), ParError> {
+        use crate::{Chunking, Scheduling};
+        // 2 x 2 matrix product, associative but not commutative
+        type M = [u64; 4];
+        let mul = |a: M, b: M| {
+            [
+                (a[0] * b[0] + a[1] * b[2]) % 1009,
+                (a[0] * b[1] + a[1] * b[3]) % 1009,
+                (a[2] * b[0] + a[3] * b[2]) % 1009,
+                (a[2] * b[1] + a[3] * b[3]) % 1009,
+            ]
+        };
+        let src: Vec<M> = (0..500).map(|i| [i % 7, 1, i % 3, 2]).collect();
+        let expected = src.iter().copied().fold([1, 0, 0, 1], mul);
+        let text: Vec<char> = "the quick brown fox jumps over the lazy dog"
+            .chars()
+            .collect();
+        for n in 1..9 {
+            let r = par_reduce(
+                &src,
+                n,
+                [1, 0, 0, 1],
+                kernel!(move |s: &[M]| s.iter().copied().fold([1, 0, 0, 1], mul)),
+                kernel!(mul),
+            )?;
+            assert_eq!(r, expected);
+            let s = par_fold(
+                &text,
+                n,
+                kernel!(String::new),
+                kernel!(|mut a: String, c: &char| {
+                    a.push(*c);
+                    a
+                }),
+                kernel!(|a: String, b: String| a + &b),
+            )?;
+            assert_eq!(s, String::from_iter(&text));
+            // sub-ranges completing out of order
+            let cfg = ParConfig::builder()
+                .threads(n)
+                .chunking(Chunking::Adaptive)
+                .scheduling(Scheduling::Dynamic)
+                .build();
+            let s = reduce_with(
+                &cfg,
+                &text[..],
+                String::new(),
+                |s: &[char]| {
+                    std::thread::sleep(std::time::Duration::from_micros(s.len() as u64 % 5));
+                    String::from_iter(s)
+                },
+                |a, b| a + &b,
+            )?;
+            assert_eq!(s, String::from_iter(&text));
+        }
+        Ok(())
+    }
+    #[test]
+    fn deterministic_test() -> Result<(), ParError> {
+        let src: Vec<f32> = (0..200_000)
+            .map(|i| (i % 1013) as f32 * 1e-3 + 1.0 / (1 + i % 7) as f32)
+            .collect();
+        let cfg = |n| ParConfig::builder().threads(n).deterministic(true).build();
+        let expected = par_sum_with(&cfg(1), &src)?.unwrap();
+        let exact: f64 = src.iter().map(|e| *e as f64).sum();
+        assert!((expected as f64 - exact).abs() < exact * 1e-6);
+        for n in [2, 3, 5, 8] {
+            assert_eq!(
+                par_sum_with(&cfg(n), &src)?.unwrap().to_bits(),
+                expected.to_bits()
+            );
+            let r = par_reduce_with(
+                &cfg(n),
+                &src,
+                0.0,
+                kernel!(|s: &[f32]| s.iter().sum::<f32>()),
+                kernel!(|a: f32, b| a + b),
+            )?;
+            assert_eq!(r.to_bits(), expected.to_bits());
+        }
+        assert_eq!(par_sum_with::<f32>(&cfg(4), &[])?, None);
+        // the other parameters of the configuration still apply
+        let stats = crate::StatsCollector::new();
+        let configured = ParConfig::builder()
+            .threads(3)
+            .chunking(crate::Chunking::Adaptive)
+            .cache_aligned(true)
+            .stats(stats.clone())
+            .deterministic(true)
+            .build();
+        let sum = par_sum_with(&configured, &src)?.unwrap();
+        assert_eq!(sum.to_bits(), expected.to_bits());
+        assert_eq!(
+            stats.chunks(),
+            src.len().div_ceil(DETERMINISTIC_CHUNK) as u64
+        );
+        let token = crate::CancelToken::new();
+        token.cancel();
+        let cancelled = ParConfig::builder()
+            .deterministic(true)
+            .cancel_token(token)
+            .build();
+        let r = par_sum_with(&cancelled, &src);
+        assert!(matches!(r, Err(ParError::Cancelled)));
+        // combined in order
+        let text: Vec<char> = (0..50_000)
+            .map(|i| (b'a' + (i % 26) as u8) as char)
+            .collect();
+        let s = par_reduce_with(
+            &cfg(3),
+            &text,
+            String::from(">"),
+            kernel!(|s: &[char]| String::from_iter(s)),
+            kernel!(|a: String, b: String| a + &b),
+        )?;
+        assert_eq!(s, String::from(">") + &String::from_iter(&text));
+        Ok(())
+    }
+    #[test]
+    fn par_sum_compensated_test() -> Result<(), ParError> {
+        // the ones are lost by a naive sum
+        let src = [1.0, 1e100, 1.0, -1e100];
+        assert_eq!(src.iter().sum::<f64>(), 0.0);
+        assert_eq!(par_sum_compensated(&src, 1)?, 2.0);
+        assert_eq!(par_sum_compensated(&src, 4)?, 2.0);
+        let src: Vec<f32> = (0..1_000_000).map(|i| 0.1 + (i % 3) as f32).collect();
+        let exact: f64 = src.iter().map(|e| *e as f64).sum();
+        for n in [1, 3, 8] {
+            let sum = par_sum_compensated(&src, n)? as f64;
+            assert!((sum - exact).abs() <= exact * 2.0 * f32::EPSILON as f64);
+        }
+        assert_eq!(par_sum_compensated::<f32>(&[], 4)?, 0.0);
+        Ok(())
+    }
+    #[test]
+    fn par_fold_test() -> Result<(), ParError> {
+        let src: Vec<u8> = (0..100).map(|i| i % 4).collect();
+        let hist = par_fold(
+            &src,
+            3,
+            kernel!(|| vec![0_usize; 4]),
+            kernel!(|mut h: Vec<usize>, e: &u8| {
+                h[*e as usize] += 1;
+                h
+            }),
+            kernel!(|a: Vec<usize>, b: Vec<usize>| a.iter().zip(&b).map(|(a, b)| a + b).collect()),
+        )?;
+        assert_eq!(hist, vec![25; 4]);
+        Ok(())
+    }
+    #[test]
+    fn par_count_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1000).collect();
+        assert_eq!(par_count(&src, 4, kernel!(|e: &u32| e % 3 == 1))?, 333);
+        assert_eq!(par_count(&src[..0], 4, kernel!(|_: &u32| true))?, 0);
+        Ok(())
+    }
+    #[test]
+    fn numeric_reductions_test() -> Result<(), ParError> {
+        let src: Vec<i64> = (-50..=100).collect();
+        assert_eq!(par_sum(&src, 4)?, Some(3775));
+        assert_eq!(par_min(&src, 4)?, Some(-50));
+        assert_eq!(par_max(&src, 3)?, Some(100));
+        assert_eq!(par_minmax(&src, 5)?, Some((-50, 100)));
+        let f = [2.5_f64, -1.0, 8.0];
+        assert_eq!(par_sum(&f, 2)?, Some(9.5));
+        assert_eq!(par_minmax(&f, 2)?, Some((-1.0, 8.0)));
+        let empty: [u8; 0] = [];
+        assert_eq!(par_sum(&empty, 4)?, None);
+        assert_eq!(par_min(&empty, 4)?, None);
+        assert_eq!(par_minmax(&empty, 4)?, None);
+        Ok(())
+    }
+    #[test]
+    fn par_histogram_test() -> Result<(), ParError> {
+        let src: Vec<u8> = (0..=255).collect();
+        let h = par_histogram(&src, 3, 4, kernel!(|e: &u8| usize::from(*e / 64)))?;
+        assert_eq!(h, vec![64; 4]);
+        let h = par_histogram(&src, 3, 2, kernel!(|e: &u8| usize::from(*e)))?;
+        assert_eq!(h, vec![1, 1]);
+        Ok(())
+    }
+}
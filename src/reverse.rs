@@ -0,0 +1,95 @@
+//! In-place reversal and rotation.
+
+use crate::{exec, split, ParError};
+use alloc::vec::Vec;
+
+//-----------------------------------------------------------------------------
+/// Reverse the order of the elements of `dest`.
+///
+/// The first half is split into sub-ranges and each thread swaps a sub-range
+/// with its mirror image in the second half.
+pub fn par_reverse<T: Send>(dest: &mut [T], num_threads: usize) -> Result<(), ParError> {
+    let (half, odd) = (dest.len() / 2, dest.len() % 2);
+    let (left, rest) = dest.split_at_mut(half);
+    // the middle element of an odd length stays in place
+    let right = &mut rest[odd..];
+    let ranges = split::ranges(half, num_threads);
+    // the mirror images, in ascending order
+    let mirrored: Vec<_> = ranges
+        .iter()
+        .rev()
+        .map(|r| half - r.end..half - r.start)
+        .collect();
+    let lefts = split::split_ranges(left, &ranges);
+    let rights = split::split_ranges(right, &mirrored).into_iter().rev();
+    exec::run(
+        num_threads,
+        lefts.into_iter().zip(rights).collect(),
+        |(l, r)| {
+            for (a, b) in l.iter_mut().zip(r.iter_mut().rev()) {
+                core::mem::swap(a, b);
+            }
+        },
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Rotate `dest` in-place such that the element at `mid` becomes the first,
+/// same as `slice::rotate_left`.
+///
+/// Implemented as three parallel reversals; panics if `mid > dest.len()`.
+pub fn par_rotate_left<T: Send>(
+    dest: &mut [T],
+    mid: usize,
+    num_threads: usize,
+) -> Result<(), ParError> {
+    assert!(mid <= dest.len(), "mid out of bounds");
+    let (a, b) = dest.split_at_mut(mid);
+    par_reverse(a, num_threads)?;
+    par_reverse(b, num_threads)?;
+    par_reverse(dest, num_threads)
+}
+
+//-----------------------------------------------------------------------------
+/// Rotate `dest` in-place such that the last `k` elements become the first,
+/// same as `slice::rotate_right`; panics if `k > dest.len()`.
+pub fn par_rotate_right<T: Send>(
+    dest: &mut [T],
+    k: usize,
+    num_threads: usize,
+) -> Result<(), ParError> {
+    assert!(k <= dest.len(), "k out of bounds");
+    par_rotate_left(dest, dest.len() - k, num_threads)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn par_reverse_test() -> Result<(), ParError> {
+        for len in [0, 1, 2, 7, 100, 101] {
+            let mut dest: Vec<usize> = (0..len).collect();
+            par_reverse(&mut dest, 3)?;
+            assert_eq!(dest, (0..len).rev().collect::<Vec<_>>());
+        }
+        Ok(())
+    }
+    #[test]
+    fn par_rotate_test() -> Result<(), ParError> {
+        let mut dest: Vec<u32> = (0..100).collect();
+        let mut expected = dest.clone();
+        par_rotate_left(&mut dest, 37, 4)?;
+        expected.rotate_left(37);
+        assert_eq!(dest, expected);
+        par_rotate_right(&mut dest, 11, 4)?;
+        expected.rotate_right(11);
+        assert_eq!(dest, expected);
+        par_rotate_left(&mut dest, 0, 4)?;
+        par_rotate_right(&mut dest, 100, 4)?;
+        assert_eq!(dest, expected);
+        Ok(())
+    }
+}
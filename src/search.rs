@@ -0,0 +1,228 @@
+//! Searching with early exit.
+
+use crate::{exec, split, ParError, PredFun};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Number of elements processed between checks of the shared state.
+const CHECK_INTERVAL: usize = 1024;
+
+//-----------------------------------------------------------------------------
+/// Return the index of the first element matching `pred`.
+///
+/// The index of the earliest match found so far is shared between the
+/// threads: a thread stops as soon as it reaches elements past it.
+pub fn par_find<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<Option<usize>, ParError> {
+    let first = AtomicUsize::new(usize::MAX);
+    exec::run_chunks(num_threads, split::chunks(src, num_threads), |(r, s)| {
+        for (i, block) in s.chunks(CHECK_INTERVAL).enumerate() {
+            let start = r.start + i * CHECK_INTERVAL;
+            if first.load(Ordering::Relaxed) < start {
+                return;
+            }
+            if let Some(p) = block.iter().position(|e| pred(e)) {
+                first.fetch_min(start + p, Ordering::Relaxed);
+                return;
+            }
+        }
+    })?;
+    let first = first.into_inner();
+    Ok((first != usize::MAX).then_some(first))
+}
+
+//-----------------------------------------------------------------------------
+/// Return `true` if any element matches `pred`; all the threads stop as soon
+/// as a match is found.
+pub fn par_any<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<bool, ParError> {
+    any(src, num_threads, |e| pred(e))
+}
+
+//-----------------------------------------------------------------------------
+/// Return `true` if all the elements match `pred`; all the threads stop as
+/// soon as a non-matching element is found.
+pub fn par_all<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: Arc<PredFun<T>>,
+) -> Result<bool, ParError> {
+    any(src, num_threads, |e| !pred(e)).map(|found| !found)
+}
+
+//-----------------------------------------------------------------------------
+/// Binary search each of `needles` in the sorted `haystack`, writing the
+/// result of `haystack.binary_search(needle)` into the matching element of
+/// `dest`; the needles are split among the threads.
+///
+/// Returns [`ParError::LengthMismatch`] if `needles` and `dest` have different
+/// lengths.
+pub fn par_search_many<T: Ord + Sync>(
+    haystack: &[T],
+    needles: &[T],
+    dest: &mut [Result<usize, usize>],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    crate::check_len(needles.len(), dest.len())?;
+    let chunks = split::chunks((needles, dest), num_threads);
+    exec::run_chunks(num_threads, chunks, |(_, (n, d))| {
+        for (d, n) in d.iter_mut().zip(n) {
+            *d = haystack.binary_search(n);
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Return `true` if `a` and `b` have the same length and equal elements; all
+/// the threads stop as soon as a difference is found.
+pub fn par_eq<T: PartialEq + Sync>(a: &[T], b: &[T], num_threads: usize) -> Result<bool, ParError> {
+    if a.len() != b.len() {
+        return Ok(false);
+    }
+    let differ = AtomicBool::new(false);
+    exec::run_chunks(
+        num_threads,
+        split::chunks((a, b), num_threads),
+        |(_, (a, b))| {
+            for (a, b) in a.chunks(CHECK_INTERVAL).zip(b.chunks(CHECK_INTERVAL)) {
+                if differ.load(Ordering::Relaxed) {
+                    return;
+                }
+                if a != b {
+                    differ.store(true, Ordering::Relaxed);
+                    return;
+                }
+            }
+        },
+    )?;
+    Ok(!differ.into_inner())
+}
+
+//-----------------------------------------------------------------------------
+/// Compare `a` and `b` lexicographically, same as `a.cmp(b)`.
+///
+/// The first differing position is searched as in [`par_find`]: threads stop
+/// once past the earliest difference found so far.
+pub fn par_cmp<T: Ord + Sync>(
+    a: &[T],
+    b: &[T],
+    num_threads: usize,
+) -> Result<std::cmp::Ordering, ParError> {
+    let len = a.len().min(b.len());
+    let first = AtomicUsize::new(usize::MAX);
+    let common = (&a[..len], &b[..len]);
+    exec::run_chunks(
+        num_threads,
+        split::chunks(common, num_threads),
+        |(r, (a, b))| {
+            let blocks = a.chunks(CHECK_INTERVAL).zip(b.chunks(CHECK_INTERVAL));
+            for (i, (a, b)) in blocks.enumerate() {
+                let start = r.start + i * CHECK_INTERVAL;
+                if first.load(Ordering::Relaxed) < start {
+                    return;
+                }
+                if let Some(p) = a.iter().zip(b).position(|(a, b)| a != b) {
+                    first.fetch_min(start + p, Ordering::Relaxed);
+                    return;
+                }
+            }
+        },
+    )?;
+    Ok(match first.into_inner() {
+        usize::MAX => a.len().cmp(&b.len()),
+        i => a[i].cmp(&b[i]),
+    })
+}
+
+// Short-circuiting search through a shared flag.
+fn any<T: Sync>(
+    src: &[T],
+    num_threads: usize,
+    pred: impl Fn(&T) -> bool + Sync,
+) -> Result<bool, ParError> {
+    let found = AtomicBool::new(false);
+    exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| {
+        for block in s.chunks(CHECK_INTERVAL) {
+            if found.load(Ordering::Relaxed) {
+                return;
+            }
+            if block.iter().any(&pred) {
+                found.store(true, Ordering::Relaxed);
+                return;
+            }
+        }
+    })?;
+    Ok(found.into_inner())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_find_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..100_000).map(|i| i % 5000).collect();
+        assert_eq!(
+            par_find(&src, 4, kernel!(|e: &u32| *e == 4999))?,
+            Some(4999)
+        );
+        assert_eq!(par_find(&src, 4, kernel!(|e: &u32| *e == 7))?, Some(7));
+        assert_eq!(par_find(&src, 4, kernel!(|e: &u32| *e > 5000))?, None);
+        Ok(())
+    }
+    #[test]
+    fn par_any_all_test() -> Result<(), ParError> {
+        let src: Vec<i32> = (0..10_000).collect();
+        assert!(par_any(&src, 4, kernel!(|e: &i32| *e == 9999))?);
+        assert!(!par_any(&src, 4, kernel!(|e: &i32| *e < 0))?);
+        assert!(par_all(&src, 3, kernel!(|e: &i32| *e >= 0))?);
+        assert!(!par_all(&src, 3, kernel!(|e: &i32| *e != 5000))?);
+        assert!(par_all(&src[..0], 3, kernel!(|_: &i32| false))?);
+        Ok(())
+    }
+    #[test]
+    fn par_cmp_test() -> Result<(), ParError> {
+        use std::cmp::Ordering::{Equal, Greater, Less};
+        let a: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+        let mut b = a.clone();
+        assert!(par_eq(&a, &b, 4)?);
+        assert_eq!(par_cmp(&a, &b, 4)?, Equal);
+        b[70_000] = 255;
+        b[90_000] = 0;
+        assert!(!par_eq(&a, &b, 4)?);
+        assert_eq!(par_cmp(&a, &b, 4)?, a.cmp(&b));
+        assert_eq!(par_cmp(&a, &b, 4)?, Less);
+        assert_eq!(par_cmp(&a, &a[..99_999], 3)?, Greater);
+        assert!(!par_eq(&a, &a[1..], 3)?);
+        assert!(par_eq::<u8>(&[], &[], 3)?);
+        Ok(())
+    }
+    #[test]
+    fn par_search_many_test() -> Result<(), ParError> {
+        let haystack: Vec<u32> = (0..1000).map(|i| i * 2).collect();
+        let needles: Vec<u32> = (0..3000).rev().collect();
+        let mut dest = vec![Ok(0); needles.len()];
+        par_search_many(&haystack, &needles, &mut dest, 4)?;
+        for (n, d) in needles.iter().zip(&dest) {
+            assert_eq!(*d, haystack.binary_search(n));
+        }
+        let r = par_search_many(&haystack, &needles[1..], &mut dest, 4);
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: 2999,
+                dest: 3000
+            })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//! Method syntax for slices.
+
+use crate::{ElemFun, InPlaceKernel, Kernel, ParConfig, ParError};
+use std::sync::Arc;
+
+//-----------------------------------------------------------------------------
+/// Extension trait exposing the parallel functions as slice methods.
+///
+/// ```rust,ignore
+/// use par_seq::prelude::*;
+/// data.par_in_place(4, kernel!(f))?;
+/// src.par_map_into(&mut dest, 4, kernel!(g))?;
+/// let v = src.par_map_collect(4, kernel!(|e: &u32| e * 2))?;
+/// ```
+pub trait ParSliceExt<T> {
+    /// Same as [`par_in_place_map`](crate::par_in_place_map).
+    fn par_in_place<K: InPlaceKernel<T> + ?Sized>(
+        &mut self,
+        num_threads: usize,
+        fr: Arc<K>,
+    ) -> Result<(), ParError>
+    where
+        T: Send;
+    /// Same as [`par_in_place_map_with`](crate::par_in_place_map_with).
+    fn par_in_place_with<K: InPlaceKernel<T> + ?Sized>(
+        &mut self,
+        cfg: &ParConfig,
+        fr: Arc<K>,
+    ) -> Result<(), ParError>
+    where
+        T: Send;
+    /// Same as [`par_map_to`](crate::par_map_to), with `self` as the source.
+    fn par_map_into<D: Send, K: Kernel<T, D> + ?Sized>(
+        &self,
+        dest: &mut [D],
+        num_threads: usize,
+        fr: Arc<K>,
+    ) -> Result<(), ParError>
+    where
+        T: Sync;
+    /// Same as [`par_map_collect`](crate::par_map_collect), with `self` as the
+    /// source.
+    fn par_map_collect<D: Send>(
+        &self,
+        num_threads: usize,
+        fr: Arc<ElemFun<T, D>>,
+    ) -> Result<Vec<D>, ParError>
+    where
+        T: Sync;
+}
+
+impl<T> ParSliceExt<T> for [T] {
+    fn par_in_place<K: InPlaceKernel<T> + ?Sized>(
+        &mut self,
+        num_threads: usize,
+        fr: Arc<K>,
+    ) -> Result<(), ParError>
+    where
+        T: Send,
+    {
+        crate::par_in_place_map(self, num_threads, fr)
+    }
+    fn par_in_place_with<K: InPlaceKernel<T> + ?Sized>(
+        &mut self,
+        cfg: &ParConfig,
+        fr: Arc<K>,
+    ) -> Result<(), ParError>
+    where
+        T: Send,
+    {
+        crate::par_in_place_map_with(cfg, self, fr)
+    }
+    fn par_map_into<D: Send, K: Kernel<T, D> + ?Sized>(
+        &self,
+        dest: &mut [D],
+        num_threads: usize,
+        fr: Arc<K>,
+    ) -> Result<(), ParError>
+    where
+        T: Sync,
+    {
+        crate::par_map_to(self, dest, num_threads, fr)
+    }
+    fn par_map_collect<D: Send>(
+        &self,
+        num_threads: usize,
+        fr: Arc<ElemFun<T, D>>,
+    ) -> Result<Vec<D>, ParError>
+    where
+        T: Sync,
+    {
+        crate::par_map_collect(self, num_threads, fr)
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    #[test]
+    fn par_slice_ext_test() -> Result<(), ParError> {
+        let mut data = [1_u16; 100];
+        data.par_in_place(
+            4,
+            kernel!(|d: &mut [u16]| d.iter_mut().for_each(|e| *e *= 3)),
+        )?;
+        let mut dest = vec![0_u32; data.len()];
+        data.par_map_into(
+            &mut dest,
+            3,
+            kernel!(|s: &[u16], d: &mut [u32]| {
+                for (d, s) in d.iter_mut().zip(s) {
+                    *d = u32::from(*s) + 1;
+                }
+            }),
+        )?;
+        assert!(dest.iter().all(|e| *e == 4));
+        let squares = dest.par_map_collect(2, kernel!(|e: &u32| e * e))?;
+        assert_eq!(squares, vec![16; 100]);
+        Ok(())
+    }
+}
@@ -0,0 +1,407 @@
+//! Parallel sorting.
+
+use crate::{exec, kernel, split, ParError};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::ops::Range;
+use std::sync::Arc;
+
+type CmpFun<'a, T> = dyn Fn(&T, &T) -> Ordering + Send + Sync + 'a;
+type KeyFun<'a, T, K> = dyn Fn(&T) -> K + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Stable parallel merge sort: the sub-ranges are sorted on their own
+/// threads, then adjacent sorted runs are merged pairwise in parallel.
+pub fn par_sort<T: Ord + Clone + Send + Sync>(
+    dest: &mut [T],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    sort(dest, num_threads, true, T::cmp)
+}
+
+/// Same as [`par_sort`], the sub-ranges are sorted with an unstable sort; the
+/// order of equal elements is not preserved.
+pub fn par_sort_unstable<T: Ord + Clone + Send + Sync>(
+    dest: &mut [T],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    sort(dest, num_threads, false, T::cmp)
+}
+
+/// Same as [`par_sort`] with a comparison function.
+pub fn par_sort_by<T: Clone + Send + Sync>(
+    dest: &mut [T],
+    num_threads: usize,
+    cmp: Arc<CmpFun<T>>,
+) -> Result<(), ParError> {
+    sort(dest, num_threads, true, |a, b| cmp(a, b))
+}
+
+/// Stable sort by key; the keys are extracted once in a parallel pass and
+/// cached, the key function is never called during the merges.
+pub fn par_sort_by_key<T, K>(
+    dest: &mut [T],
+    num_threads: usize,
+    key: Arc<KeyFun<T, K>>,
+) -> Result<(), ParError>
+where
+    T: Clone + Send + Sync,
+    K: Ord + Clone + Send + Sync,
+{
+    let mut keys: Vec<Option<K>> = (0..dest.len()).map(|_| None).collect();
+    let key_fun = |s: &[T], d: &mut [Option<K>]| {
+        for (d, s) in d.iter_mut().zip(s) {
+            *d = Some(key(s));
+        }
+    };
+    crate::par_map_to(dest, &mut keys, num_threads, kernel!(key_fun))?;
+    let mut order: Vec<(K, usize)> = keys.into_iter().flatten().zip(0..).collect();
+    sort(&mut order, num_threads, true, |a, b| a.0.cmp(&b.0))?;
+    // move the elements to their sorted position
+    let src = dest.to_vec();
+    let chunks = split::chunks((&order[..], dest), num_threads);
+    exec::run_chunks(num_threads, chunks, |(_, (o, d))| {
+        for (d, (_, i)) in d.iter_mut().zip(o) {
+            d.clone_from(&src[*i]);
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Stable merge of the sorted slices `a` and `b` into `dest`; on ties the
+/// elements of `a` come first.
+///
+/// `dest` is split into sub-ranges and a binary search finds the elements
+/// of `a` and `b` merged into each sub-range, which are then merged
+/// independently. Returns [`ParError::LengthMismatch`] if
+/// `dest.len() != a.len() + b.len()`.
+pub fn par_merge<T: Ord + Clone + Send + Sync>(
+    a: &[T],
+    b: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+) -> Result<(), ParError> {
+    merge_into(a, b, dest, num_threads, T::cmp)
+}
+
+/// Same as [`par_merge`] with a comparison function.
+pub fn par_merge_by<T: Clone + Send + Sync>(
+    a: &[T],
+    b: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    cmp: Arc<CmpFun<T>>,
+) -> Result<(), ParError> {
+    merge_into(a, b, dest, num_threads, |x, y| cmp(x, y))
+}
+
+fn merge_into<T, F>(
+    a: &[T],
+    b: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    cmp: F,
+) -> Result<(), ParError>
+where
+    T: Clone + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    crate::check_len(a.len() + b.len(), dest.len())?;
+    let parts = co_split(a, b, dest, num_threads, &cmp);
+    exec::run(num_threads, parts, |(a, b, d)| merge(a, b, d, &cmp))?;
+    Ok(())
+}
+
+// Split the merge of `a` and `b` into `dest` into the independent merges of
+// at most `parts` consecutive sub-ranges of `dest`.
+fn co_split<'a, T: Send + Sync>(
+    a: &'a [T],
+    b: &'a [T],
+    dest: &'a mut [T],
+    parts: usize,
+    cmp: &impl Fn(&T, &T) -> Ordering,
+) -> Vec<(&'a [T], &'a [T], &'a mut [T])> {
+    let out = split::ranges(dest.len(), parts);
+    let mut i = 0;
+    let (a_ranges, b_ranges): (Vec<_>, Vec<_>) = out
+        .iter()
+        .map(|r| {
+            let (start, end) = (i, co_rank(r.end, a, b, cmp));
+            i = end;
+            (start..end, r.start - start..r.end - end)
+        })
+        .unzip();
+    let a_parts = split::split_ranges(a, &a_ranges);
+    let b_parts = split::split_ranges(b, &b_ranges);
+    let d_parts = split::split_ranges(dest, &out);
+    a_parts
+        .into_iter()
+        .zip(b_parts)
+        .zip(d_parts)
+        .map(|((a, b), d)| (a, b, d))
+        .collect()
+}
+
+// Number of elements of `a` among the first `k` elements of the stable
+// merge of `a` and `b`.
+fn co_rank<T>(k: usize, a: &[T], b: &[T], cmp: impl Fn(&T, &T) -> Ordering) -> usize {
+    let (mut lo, mut hi) = (k.saturating_sub(b.len()), k.min(a.len()));
+    while lo < hi {
+        let i = (lo + hi) / 2;
+        // `a[i]` precedes `b[k - i - 1]`: more elements come from `a`
+        if cmp(&a[i], &b[k - i - 1]) != Ordering::Greater {
+            lo = i + 1;
+        } else {
+            hi = i;
+        }
+    }
+    lo
+}
+
+//-----------------------------------------------------------------------------
+/// The `k` largest elements of `src` according to `cmp`, largest first; all
+/// the elements if `k >= src.len()`. Which of several equal elements are
+/// returned is unspecified.
+///
+/// Each thread keeps the `k` largest elements of its sub-range in a bounded
+/// heap, and the `k` largest of the kept elements are selected at the end:
+/// `src` is read once, and only `k` elements per thread are sorted.
+///
+/// ```rust,ignore
+/// let best = par_top_k(&scores, 100, 8, kernel!(|a: &f32, b: &f32| a.total_cmp(b)))?;
+/// ```
+pub fn par_top_k<T: Clone + Send + Sync>(
+    src: &[T],
+    k: usize,
+    num_threads: usize,
+    cmp: Arc<CmpFun<T>>,
+) -> Result<Vec<T>, ParError> {
+    let cmp = &*cmp;
+    let heaps = exec::run_chunks(num_threads, split::chunks(src, num_threads), |(_, s)| {
+        let mut heap = BinaryHeap::with_capacity(k.min(s.len()) + 1);
+        for e in s {
+            if heap.len() < k {
+                heap.push(Kept(e, cmp));
+            } else if let Some(mut min) = heap.peek_mut() {
+                if cmp(e, min.0) == Ordering::Greater {
+                    *min = Kept(e, cmp);
+                }
+            }
+        }
+        heap.into_iter().map(|e| e.0).collect::<Vec<_>>()
+    })?;
+    let mut kept: Vec<&T> = heaps.into_iter().flatten().collect();
+    kept.sort_by(|a, b| cmp(b, a));
+    Ok(kept.into_iter().take(k).cloned().collect())
+}
+
+// Element of the heaps of `par_top_k`, ordered in reverse so that the top of
+// a heap is the smallest element kept.
+struct Kept<'a, T, F: ?Sized>(&'a T, &'a F);
+
+impl<T, F: Fn(&T, &T) -> Ordering + ?Sized> Ord for Kept<'_, T, F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.1)(other.0, self.0)
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering + ?Sized> PartialOrd for Kept<'_, T, F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering + ?Sized> PartialEq for Kept<'_, T, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T, F: Fn(&T, &T) -> Ordering + ?Sized> Eq for Kept<'_, T, F> {}
+
+//-----------------------------------------------------------------------------
+pub(crate) fn sort<T, F>(
+    dest: &mut [T],
+    num_threads: usize,
+    stable: bool,
+    cmp: F,
+) -> Result<(), ParError>
+where
+    T: Clone + Send + Sync,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let mut runs = split::ranges(dest.len(), num_threads);
+    exec::run(num_threads, split::split_ranges(&mut *dest, &runs), |d| {
+        if stable {
+            d.sort_by(&cmp)
+        } else {
+            d.sort_unstable_by(&cmp)
+        }
+    })?;
+    if runs.len() < 2 {
+        return Ok(());
+    }
+    // ping-pong between the sequence and a buffer
+    let mut buf = dest.to_vec();
+    let mut in_buf = false;
+    while runs.len() > 1 {
+        let (from, to): (&[T], &mut [T]) = if in_buf {
+            (&buf, &mut *dest)
+        } else {
+            (&*dest, &mut buf)
+        };
+        let merged: Vec<Range<usize>> = runs
+            .chunks(2)
+            .map(|p| p[0].start..p[p.len() - 1].end)
+            .collect();
+        let mids: Vec<usize> = runs.chunks(2).map(|p| p[0].len()).collect();
+        // the threads are shared among the pairs so that the last rounds,
+        // with fewer pairs than threads, still use all of them
+        let per_pair = (crate::resolve_threads(num_threads) / merged.len()).max(1);
+        let parts: Vec<_> = split::split_ranges((from, to), &merged)
+            .into_iter()
+            .zip(mids)
+            .flat_map(|((s, d), mid)| co_split(&s[..mid], &s[mid..], d, per_pair, &cmp))
+            .collect();
+        exec::run(num_threads, parts, |(a, b, d)| merge(a, b, d, &cmp))?;
+        runs = merged;
+        in_buf = !in_buf;
+    }
+    if in_buf {
+        let parts = split::chunks((&buf[..], dest), num_threads);
+        exec::run_chunks(num_threads, parts, |(_, (s, d))| d.clone_from_slice(s))?;
+    }
+    Ok(())
+}
+
+// Stable merge of two sorted runs into `dest`, `a.len() + b.len() == dest.len()`.
+pub(crate) fn merge<T: Clone>(a: &[T], b: &[T], dest: &mut [T], cmp: impl Fn(&T, &T) -> Ordering) {
+    let (mut i, mut j) = (0, 0);
+    for d in dest.iter_mut() {
+        // take from `a` on ties to keep the sort stable
+        let take_a = j == b.len() || (i < a.len() && cmp(&b[j], &a[i]) != Ordering::Less);
+        if take_a {
+            d.clone_from(&a[i]);
+            i += 1;
+        } else {
+            d.clone_from(&b[j]);
+            j += 1;
+        }
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // simple deterministic pseudo-random sequence
+    fn lcg(n: usize) -> Vec<u32> {
+        let mut x = 12345_u32;
+        (0..n)
+            .map(|_| {
+                x = x.wrapping_mul(1103515245).wrapping_add(12345);
+                x >> 16
+            })
+            .collect()
+    }
+    #[test]
+    fn par_sort_test() -> Result<(), ParError> {
+        for threads in [1, 2, 3, 7] {
+            let mut data = lcg(10_001);
+            let mut expected = data.clone();
+            expected.sort();
+            par_sort(&mut data, threads)?;
+            assert_eq!(data, expected);
+            let mut data = lcg(999);
+            par_sort_unstable(&mut data, threads)?;
+            assert!(data.windows(2).all(|w| w[0] <= w[1]));
+        }
+        Ok(())
+    }
+    #[test]
+    fn par_merge_test() -> Result<(), ParError> {
+        for (na, nb) in [(0, 10), (10, 0), (1000, 37), (500, 500)] {
+            let mut a: Vec<u32> = lcg(na).into_iter().map(|e| e % 100).collect();
+            let mut b: Vec<u32> = lcg(nb + 3)[3..].iter().map(|e| e % 100).collect();
+            a.sort();
+            b.sort();
+            let mut expected = [a.clone(), b.clone()].concat();
+            expected.sort();
+            let mut dest = vec![0; na + nb];
+            par_merge(&a, &b, &mut dest, 4)?;
+            assert_eq!(dest, expected);
+        }
+        // stable: on ties the elements of `a` come first
+        let a = [(1, 'a'), (2, 'a'), (2, 'a')];
+        let b = [(0, 'b'), (2, 'b'), (3, 'b')];
+        let mut dest = [(0, ' '); 6];
+        par_merge_by(
+            &a,
+            &b,
+            &mut dest,
+            3,
+            kernel!(|x: &(u32, char), y: &(u32, char)| x.0.cmp(&y.0)),
+        )?;
+        assert_eq!(dest.map(|e| e.1), ['b', 'a', 'a', 'a', 'b', 'b']);
+        let r = par_merge_by(
+            &a,
+            &b[1..],
+            &mut dest,
+            3,
+            kernel!(|x: &(u32, char), y: &(u32, char)| x.0.cmp(&y.0)),
+        );
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 5, dest: 6 })
+        ));
+        Ok(())
+    }
+    #[test]
+    fn par_sort_by_stable_test() -> Result<(), ParError> {
+        // sort pairs by first element only, second records original position
+        let mut data: Vec<(u32, usize)> = lcg(5000).into_iter().map(|e| e % 10).zip(0..).collect();
+        par_sort_by(
+            &mut data,
+            5,
+            kernel!(|a: &(u32, usize), b: &(u32, usize)| a.0.cmp(&b.0)),
+        )?;
+        assert!(data
+            .windows(2)
+            .all(|w| w[0].0 < w[1].0 || (w[0].0 == w[1].0 && w[0].1 < w[1].1)));
+        Ok(())
+    }
+    #[test]
+    fn par_sort_by_key_test() -> Result<(), ParError> {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let c = &calls;
+        let mut data: Vec<String> = lcg(3000).iter().map(|e| e.to_string()).collect();
+        let mut expected = data.clone();
+        expected.sort_by_key(|s| s.len());
+        let key_fun = move |s: &String| {
+            c.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            s.len()
+        };
+        par_sort_by_key(&mut data, 4, kernel!(key_fun))?;
+        assert_eq!(data, expected);
+        assert_eq!(calls.into_inner(), 3000);
+        Ok(())
+    }
+    #[test]
+    fn par_top_k_test() -> Result<(), ParError> {
+        let data = lcg(100_000);
+        let mut expected = data.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        for n in [1, 3, 8] {
+            let top = par_top_k(&data, 100, n, kernel!(|a: &u32, b: &u32| a.cmp(b)))?;
+            assert_eq!(top, expected[..100]);
+        }
+        let smallest = par_top_k(&data, 5, 4, kernel!(|a: &u32, b: &u32| b.cmp(a)))?;
+        assert!(smallest.iter().eq(expected.iter().rev().take(5)));
+        let all = par_top_k(&data[..10], 20, 4, kernel!(|a: &u32, b: &u32| a.cmp(b)))?;
+        assert_eq!(all.len(), 10);
+        assert!(par_top_k(&data, 0, 4, kernel!(|a: &u32, b: &u32| a.cmp(b)))?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+//! Executors running the worker threads.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+//-----------------------------------------------------------------------------
+/// Body of a worker, borrowing the data of the call.
+pub type Job<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// Executor running the workers of a parallel call, selected with
+/// [`ParConfigBuilder::spawner`](crate::ParConfigBuilder::spawner).
+///
+/// Lets embedders route the work onto an existing thread infrastructure.
+/// Jobs never unwind: kernel panics are caught and reported by the call.
+///
+/// ```rust,ignore
+/// struct Serial;
+/// impl Spawner for Serial {
+///     fn run(&self, jobs: Vec<Job<'_>>) {
+///         jobs.into_iter().for_each(|j| j());
+///     }
+/// }
+/// let cfg = ParConfig::builder().spawner(Arc::new(Serial)).build();
+/// ```
+pub trait Spawner: Send + Sync {
+    /// Run `jobs`, one per worker, and return once all of them have completed.
+    ///
+    /// Jobs pulling sub-ranges from a shared queue make progress even when
+    /// run one after the other, but workers should run concurrently for the
+    /// call to be parallel. A job which is never run is reported as a panic.
+    fn run(&self, jobs: Vec<Job<'_>>);
+}
+
+//-----------------------------------------------------------------------------
+/// Spawner running each job on its own scoped `std` thread.
+///
+/// The default spawner behaves the same and also applies the thread name,
+/// stack size and core pinning options of the configuration.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdSpawner;
+
+#[cfg(feature = "std")]
+impl Spawner for StdSpawner {
+    fn run(&self, jobs: Vec<Job<'_>>) {
+        std::thread::scope(|s| {
+            for j in jobs {
+                s.spawn(j);
+            }
+        });
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{kernel, par_in_place_map_with, ParConfig, ParError};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    // Runs the jobs one after the other on the calling thread.
+    #[derive(Default)]
+    struct Serial(AtomicUsize);
+    impl Spawner for Serial {
+        fn run(&self, jobs: Vec<Job<'_>>) {
+            self.0.fetch_add(jobs.len(), Ordering::Relaxed);
+            jobs.into_iter().for_each(|j| j());
+        }
+    }
+    struct Dropping;
+    impl Spawner for Dropping {
+        fn run(&self, _: Vec<Job<'_>>) {}
+    }
+    #[test]
+    fn spawner_test() -> Result<(), ParError> {
+        let serial = Arc::new(Serial::default());
+        let cfg = ParConfig::builder()
+            .threads(3)
+            .chunk_size(10)
+            .spawner(serial.clone())
+            .build();
+        let caller = std::thread::current().id();
+        let kernel_fun = |d: &mut [u32]| {
+            assert_eq!(std::thread::current().id(), caller);
+            d.iter_mut().for_each(|e| *e += 1);
+        };
+        let mut dest: Vec<u32> = (0..100).collect();
+        par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        assert!(dest.iter().enumerate().all(|(i, e)| *e == i as u32 + 1));
+        assert_eq!(serial.0.load(Ordering::Relaxed), 3);
+        // the calling thread is not left marked as a worker
+        #[cfg(feature = "std")]
+        {
+            let kernel_fun = |_: &mut [u32]| assert_ne!(std::thread::current().id(), caller);
+            crate::par_in_place_map(&mut dest, 2, kernel!(kernel_fun))?;
+            let cfg = ParConfig::builder()
+                .threads(2)
+                .spawner(Arc::new(StdSpawner))
+                .build();
+            par_in_place_map_with(&cfg, &mut dest, kernel!(kernel_fun))?;
+        }
+        let cfg = ParConfig::builder()
+            .threads(2)
+            .spawner(Arc::new(Dropping))
+            .build();
+        let r = par_in_place_map_with(&cfg, &mut dest, kernel!(|d: &mut [u32]| d.fill(0)));
+        assert!(matches!(r, Err(ParError::Panic(_))));
+        assert!(dest.iter().all(|e| *e != 0));
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+//! Splitting of sequences into sub-ranges.
+
+use crate::ParConfig;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+//-----------------------------------------------------------------------------
+// Data which can be split into disjoint consecutive parts and sent to
+// worker threads: slices, mutable slices and tuples of them.
+pub(crate) trait Split: Sized + Send {
+    // Number of elements; for tuples the length of the shortest member.
+    fn len(&self) -> usize;
+    // Size in bytes of an element; for tuples the smallest non-zero size.
+    fn elem_size() -> usize;
+    // Address of the first element; for tuples the one of the last member,
+    // the destination of the map kernels.
+    fn addr(&self) -> usize;
+    // Split into `[0, mid)` and `[mid, len)`.
+    fn split_at(self, mid: usize) -> (Self, Self);
+}
+
+impl<T: Sync> Split for &[T] {
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+    fn elem_size() -> usize {
+        core::mem::size_of::<T>()
+    }
+    fn addr(&self) -> usize {
+        self.as_ptr() as usize
+    }
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        <[T]>::split_at(self, mid)
+    }
+}
+
+impl<T: Send> Split for &mut [T] {
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+    fn elem_size() -> usize {
+        core::mem::size_of::<T>()
+    }
+    fn addr(&self) -> usize {
+        self.as_ptr() as usize
+    }
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        self.split_at_mut(mid)
+    }
+}
+
+impl<A: Split, B: Split> Split for (A, B) {
+    fn len(&self) -> usize {
+        self.0.len().min(self.1.len())
+    }
+    fn elem_size() -> usize {
+        min_size(A::elem_size(), B::elem_size())
+    }
+    fn addr(&self) -> usize {
+        self.1.addr()
+    }
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        let (a0, a1) = self.0.split_at(mid);
+        let (b0, b1) = self.1.split_at(mid);
+        ((a0, b0), (a1, b1))
+    }
+}
+
+// Smallest non-zero size, `0` if both are zero.
+fn min_size(a: usize, b: usize) -> usize {
+    match (a, b) {
+        (0, s) | (s, 0) => s,
+        _ => a.min(b),
+    }
+}
+
+// Larger tuples, used by the multi-source kernels
+macro_rules! impl_split_tuple {
+    ($($P:ident $p:ident $q:ident),+) => {
+        impl<$($P: Split),+> Split for ($($P,)+) {
+            fn len(&self) -> usize {
+                let ($($p,)+) = self;
+                usize::MAX $(.min($p.len()))+
+            }
+            fn elem_size() -> usize {
+                [$($P::elem_size()),+].into_iter().fold(0, min_size)
+            }
+            fn addr(&self) -> usize {
+                let ($($p,)+) = self;
+                [$($p.addr()),+].into_iter().last().unwrap_or(0)
+            }
+            fn split_at(self, mid: usize) -> (Self, Self) {
+                let ($($p,)+) = self;
+                $(let ($p, $q) = $p.split_at(mid);)+
+                (($($p,)+), ($($q,)+))
+            }
+        }
+    };
+}
+impl_split_tuple!(A a0 a1, B b0 b1, C c0 c1);
+impl_split_tuple!(A a0 a1, B b0 b1, C c0 c1, D d0 d1);
+impl_split_tuple!(A a0 a1, B b0 b1, C c0 c1, D d0 d1, E e0 e1);
+
+//-----------------------------------------------------------------------------
+// Split `[0, len)` into `min(num_threads, len)` consecutive ranges whose
+// lengths differ by at most one; no ranges for an empty sequence.
+pub(crate) fn ranges(len: usize, num_threads: usize) -> Vec<Range<usize>> {
+    let n = crate::resolve_threads(num_threads).min(len);
+    if n == 0 {
+        return Vec::new();
+    }
+    let (base, rem) = (len / n, len % n);
+    let mut start = 0;
+    (0..n)
+        .map(|i| {
+            let end = start + base + usize::from(i < rem);
+            let r = start..end;
+            start = end;
+            r
+        })
+        .collect()
+}
+
+// Split `[0, len)` into consecutive ranges of `chunk_size` elements, the last
+// one holding the remainder.
+pub(crate) fn fixed_ranges(len: usize, chunk_size: usize) -> Vec<Range<usize>> {
+    let cs = chunk_size.max(1);
+    (0..len.div_ceil(cs))
+        .map(|i| i * cs..((i + 1) * cs).min(len))
+        .collect()
+}
+
+// Split `[0, len)` into consecutive ranges holding the remaining elements
+// divided by the number of threads, but at least `min_size` elements.
+pub(crate) fn guided_ranges(len: usize, num_threads: usize, min_size: usize) -> Vec<Range<usize>> {
+    let n = crate::resolve_threads(num_threads);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < len {
+        let size = (len - start).div_ceil(n).max(min_size);
+        let end = (start + size).min(len);
+        ranges.push(start..end);
+        start = end;
+    }
+    ranges
+}
+
+// Split `[0, len)` into at most `parts` consecutive non-empty ranges whose
+// total `weight` is approximately equal.
+pub(crate) fn weighted_ranges(
+    len: usize,
+    parts: usize,
+    weight: &dyn Fn(usize) -> u64,
+) -> Vec<Range<usize>> {
+    let parts = parts.clamp(1, len.max(1)) as u128;
+    let total: u128 = (0..len).map(|i| u128::from(weight(i))).sum();
+    if total == 0 {
+        return ranges(len, parts as usize);
+    }
+    let mut ranges = Vec::new();
+    let (mut start, mut acc, mut k) = (0, 0_u128, 1);
+    for i in 0..len {
+        acc += u128::from(weight(i));
+        // the range ends once its share of the total weight is reached
+        if k < parts && acc * parts >= total * k {
+            ranges.push(start..i + 1);
+            start = i + 1;
+            while k < parts && acc * parts >= total * k {
+                k += 1;
+            }
+        }
+    }
+    if start < len {
+        ranges.push(start..len);
+    }
+    ranges
+}
+
+// Move the boundaries between consecutive `ranges` covering `[0, len)` up to
+// `offset` plus multiples of `step` elements, dropping the ranges left empty.
+pub(crate) fn align_ranges(
+    ranges: Vec<Range<usize>>,
+    step: usize,
+    offset: usize,
+) -> Vec<Range<usize>> {
+    let len = ranges.last().map_or(0, |r| r.end);
+    let mut start = 0;
+    ranges
+        .into_iter()
+        .filter_map(|r| {
+            let end = match r.end.checked_sub(offset) {
+                Some(e) => offset + e.next_multiple_of(step.max(1)),
+                None => offset,
+            };
+            let end = end.min(len);
+            let r = start..end;
+            start = end;
+            (!r.is_empty()).then_some(r)
+        })
+        .collect()
+}
+
+// Split `data` into the parts matching consecutive `ranges` starting at 0;
+// the last part also receives any element past the end of the last range.
+pub(crate) fn split_ranges<P: Split>(mut data: P, ranges: &[Range<usize>]) -> Vec<P> {
+    let mut parts = Vec::with_capacity(ranges.len());
+    for (i, r) in ranges.iter().enumerate() {
+        if i + 1 == ranges.len() {
+            parts.push(data);
+            break;
+        }
+        let (head, tail) = data.split_at(r.len());
+        parts.push(head);
+        data = tail;
+    }
+    parts
+}
+
+// Split `data` into one part per thread, returning each part with its range.
+pub(crate) fn chunks<P: Split>(data: P, num_threads: usize) -> Vec<(Range<usize>, P)> {
+    let ranges = ranges(data.len(), num_threads);
+    zip_ranges(data, ranges)
+}
+
+// Split `data` into the sub-ranges selected by `cfg`.
+pub(crate) fn chunks_with<P: Split>(cfg: &ParConfig, data: P) -> Vec<(Range<usize>, P)> {
+    let ranges = cfg.ranges(data.len(), P::elem_size(), data.addr());
+    zip_ranges(data, ranges)
+}
+
+pub(crate) fn zip_ranges<P: Split>(data: P, ranges: Vec<Range<usize>>) -> Vec<(Range<usize>, P)> {
+    let parts = split_ranges(data, &ranges);
+    ranges.into_iter().zip(parts).collect()
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    #[test]
+    fn ranges_test() {
+        assert_eq!(ranges(64, 3), vec![0..22, 22..43, 43..64]);
+        assert_eq!(ranges(3, 8), vec![0..1, 1..2, 2..3]);
+        assert_eq!(ranges(5, 4), vec![0..2, 2..3, 3..4, 4..5]);
+        assert!(ranges(0, 4).is_empty());
+        assert_eq!(fixed_ranges(10, 4), vec![0..4, 4..8, 8..10]);
+        assert!(fixed_ranges(0, 4).is_empty());
+        assert_eq!(
+            guided_ranges(100, 4, 5),
+            vec![
+                0..25,
+                25..44,
+                44..58,
+                58..69,
+                69..77,
+                77..83,
+                83..88,
+                88..93,
+                93..98,
+                98..100
+            ]
+        );
+        assert!(guided_ranges(0, 4, 1).is_empty());
+        // cost growing linearly with the index
+        assert_eq!(
+            weighted_ranges(100, 4, &|i| i as u64),
+            vec![0..51, 51..71, 71..87, 87..100]
+        );
+        assert_eq!(weighted_ranges(10, 3, &|_| 1), vec![0..4, 4..7, 7..10]);
+        assert_eq!(weighted_ranges(5, 2, &|_| 0), vec![0..3, 3..5]);
+        assert!(weighted_ranges(0, 4, &|_| 1).is_empty());
+        assert_eq!(
+            align_ranges(ranges(100, 3), 16, 0),
+            vec![0..48, 48..80, 80..100]
+        );
+        assert_eq!(align_ranges(ranges(20, 4), 16, 0), vec![0..16, 16..20]);
+        assert_eq!(
+            align_ranges(ranges(100, 3), 16, 5),
+            vec![0..37, 37..69, 69..100]
+        );
+    }
+    #[test]
+    fn chunks_test() {
+        let src = [1, 2, 3, 4, 5];
+        let mut dest = [0; 5];
+        let c = chunks((&src[..], &mut dest[..]), 2);
+        assert_eq!(c.len(), 2);
+        assert_eq!(c[0].0, 0..3);
+        assert_eq!(c[1].1 .0, &[4, 5]);
+        assert_eq!(c[1].1 .1.len(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! Kernels with per-thread mutable state.
+
+use crate::{exec, split, FactoryFun, ParError};
+use std::sync::Arc;
+
+type StateKernelFun2<'a, S, T> = dyn Fn(&mut S, &[T], &mut [T]) + Send + Sync + 'a;
+type StateKernelFun1<'a, S, T> = dyn Fn(&mut S, &mut [T]) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_map`](crate::par_map), each worker thread creates its own
+/// state with `init` and passes it to the kernel for every sub-range it
+/// processes: `Fn(&mut S, &[T], &mut [T])`.
+///
+/// Useful for scratch buffers or random number generators, the state never
+/// leaves the thread which created it.
+pub fn par_map_stateful<T: Send + Sync, S>(
+    src: &[T],
+    dest: &mut [T],
+    num_threads: usize,
+    init: Arc<FactoryFun<S>>,
+    fr: Arc<StateKernelFun2<S, T>>,
+) -> Result<(), ParError> {
+    crate::check_len(src.len(), dest.len())?;
+    exec::run_stateful(
+        num_threads,
+        split::chunks((src, dest), num_threads),
+        |_| init(),
+        |st, (_, (s, d))| fr(st, s, d),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_in_place_map`](crate::par_in_place_map) with one state per
+/// worker thread, see [`par_map_stateful`]: `Fn(&mut S, &mut [T])`.
+pub fn par_in_place_map_stateful<T: Send, S>(
+    dest: &mut [T],
+    num_threads: usize,
+    init: Arc<FactoryFun<S>>,
+    fr: Arc<StateKernelFun1<S, T>>,
+) -> Result<(), ParError> {
+    exec::run_stateful(
+        num_threads,
+        split::chunks(dest, num_threads),
+        |_| init(),
+        |st, (_, d)| fr(st, d),
+    )?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    #[test]
+    fn par_stateful_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..100).collect();
+        let mut dest = vec![0; 100];
+        let states = AtomicUsize::new(0);
+        let init = || {
+            states.fetch_add(1, Ordering::Relaxed);
+            Vec::<u32>::new()
+        };
+        // scratch buffer reused across elements
+        let kernel_fun = |buf: &mut Vec<u32>, s: &[u32], d: &mut [u32]| {
+            buf.clear();
+            buf.extend(s.iter().map(|e| e * 2));
+            d.copy_from_slice(buf);
+        };
+        par_map_stateful(&src, &mut dest, 4, kernel!(init), kernel!(kernel_fun))?;
+        assert_eq!(dest, (0..200).step_by(2).collect::<Vec<_>>());
+        assert_eq!(states.swap(0, Ordering::Relaxed), 4);
+        // one counter per thread
+        let mut dest = vec![0_usize; 10];
+        let init = || {
+            states.fetch_add(1, Ordering::Relaxed);
+            0_usize
+        };
+        let kernel_fun = |n: &mut usize, d: &mut [usize]| {
+            *n += 1;
+            d.fill(*n);
+        };
+        par_in_place_map_stateful(&mut dest, 2, kernel!(init), kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 1));
+        assert_eq!(states.into_inner(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! Per-worker execution statistics.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+//-----------------------------------------------------------------------------
+/// Statistics of a single worker.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Number of sub-ranges run by the worker.
+    pub chunks: usize,
+    /// Time spent running kernels.
+    pub busy: Duration,
+    /// Time spent not running kernels between the start and the end of the
+    /// call: waiting for the next sub-range or for the other workers.
+    pub wait: Duration,
+}
+
+//-----------------------------------------------------------------------------
+/// Statistics of a parallel call, see [`StatsCollector`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParStats {
+    /// Wall-clock duration of the call.
+    pub elapsed: Duration,
+    /// Statistics of each worker, by worker index.
+    pub workers: Vec<WorkerStats>,
+}
+
+impl ParStats {
+    /// Fraction of the worker time spent running kernels, `1.0` when the load
+    /// is perfectly balanced.
+    pub fn utilization(&self) -> f64 {
+        let total = self.elapsed.as_secs_f64() * self.workers.len() as f64;
+        if total == 0.0 {
+            return 1.0;
+        }
+        let busy: f64 = self.workers.iter().map(|w| w.busy.as_secs_f64()).sum();
+        (busy / total).min(1.0)
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Shared destination of the statistics of the calls run with a
+/// [`ParConfig`](crate::ParConfig).
+///
+/// Clones refer to the same collector: pass one to
+/// [`ParConfigBuilder::stats`](crate::ParConfigBuilder::stats) and read the
+/// statistics of the last completed call with [`StatsCollector::stats`].
+/// Timing is only measured when a collector is set.
+///
+/// ```rust,ignore
+/// let stats = StatsCollector::new();
+/// let cfg = ParConfig::builder().threads(8).stats(stats.clone()).build();
+/// par_map_with(&cfg, &src, &mut dest, kernel!(kernel_fun))?;
+/// println!("{:.0}% busy", stats.stats().unwrap().utilization() * 100.0);
+/// ```
+///
+/// The collector also keeps counters over all the calls, updated as the
+/// kernels complete, to be exported as metrics:
+///
+/// ```rust,ignore
+/// gauge.set(stats.busy_threads() as i64);
+/// busy_seconds.set(stats.busy_time().as_secs_f64());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StatsCollector(Arc<Collector>);
+
+#[derive(Debug, Default)]
+struct Collector {
+    last: Mutex<Option<ParStats>>,
+    calls: AtomicU64,
+    chunks: AtomicU64,
+    busy_nanos: AtomicU64,
+    busy_threads: AtomicUsize,
+}
+
+impl StatsCollector {
+    /// Create a collector without statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Statistics of the last completed call, `None` before the first one.
+    pub fn stats(&self) -> Option<ParStats> {
+        self.0
+            .last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+    /// Number of completed calls.
+    pub fn calls(&self) -> u64 {
+        self.0.calls.load(Ordering::Relaxed)
+    }
+    /// Number of sub-ranges run by all the calls, including the running ones.
+    pub fn chunks(&self) -> u64 {
+        self.0.chunks.load(Ordering::Relaxed)
+    }
+    /// Time spent running kernels by all the workers of all the calls.
+    pub fn busy_time(&self) -> Duration {
+        Duration::from_nanos(self.0.busy_nanos.load(Ordering::Relaxed))
+    }
+    /// Number of workers running a kernel at the moment.
+    pub fn busy_threads(&self) -> usize {
+        self.0.busy_threads.load(Ordering::Relaxed)
+    }
+    pub(crate) fn chunk_started(&self) {
+        self.0.busy_threads.fetch_add(1, Ordering::Relaxed);
+    }
+    pub(crate) fn chunk_done(&self, busy: Duration) {
+        let nanos = u64::try_from(busy.as_nanos()).unwrap_or(u64::MAX);
+        self.0.busy_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.0.chunks.fetch_add(1, Ordering::Relaxed);
+        self.0.busy_threads.fetch_sub(1, Ordering::Relaxed);
+    }
+    pub(crate) fn record(&self, stats: ParStats) {
+        *self.0.last.lock().unwrap_or_else(|e| e.into_inner()) = Some(stats);
+        self.0.calls.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn utilization_test() {
+        let worker = |busy| WorkerStats {
+            chunks: 1,
+            busy: Duration::from_millis(busy),
+            wait: Duration::from_millis(100 - busy),
+        };
+        let stats = ParStats {
+            elapsed: Duration::from_millis(100),
+            workers: vec![worker(100), worker(50)],
+        };
+        assert!((stats.utilization() - 0.75).abs() < 1e-9);
+        assert_eq!(ParStats::default().utilization(), 1.0);
+        let collector = StatsCollector::new();
+        assert!(collector.stats().is_none());
+        collector.clone().record(stats.clone());
+        assert_eq!(collector.stats(), Some(stats));
+        assert_eq!(collector.calls(), 1);
+    }
+    #[test]
+    fn counters_test() -> Result<(), crate::ParError> {
+        use crate::{kernel, par_in_place_map_with, ParConfig};
+        let stats = StatsCollector::new();
+        let cfg = ParConfig::builder().threads(4).stats(stats.clone()).build();
+        let mut dest = vec![0_u32; 1000];
+        let busy = stats.clone();
+        par_in_place_map_with(
+            &cfg,
+            &mut dest,
+            kernel!(move |d: &mut [u32]| {
+                d.fill(busy.busy_threads() as u32);
+                std::thread::sleep(Duration::from_millis(1));
+            }),
+        )?;
+        par_in_place_map_with(&cfg, &mut dest[..10], kernel!(|_: &mut [u32]| {}))?;
+        assert!(dest.iter().all(|b| (1..=4).contains(b)));
+        assert_eq!(stats.calls(), 2);
+        assert_eq!(stats.chunks(), 8);
+        assert!(stats.busy_time() >= Duration::from_millis(4));
+        assert_eq!(stats.busy_threads(), 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+//! Stencil kernels reading a halo around each sub-range.
+
+use crate::{exec, split, ParError};
+use std::sync::Arc;
+
+type StencilKernelFun<'a, T> = dyn Fn(&[T], usize, &mut [T]) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Map `src` into `dest` with a kernel that also reads up to `halo` elements
+/// on either side of its sub-range.
+///
+/// The kernel receives `Fn(padded, core_offset, core)`: `padded` is the source
+/// sub-range extended by the halo, `core` the writable destination sub-range
+/// and `core_offset` the position in `padded` of the element matching
+/// `core[0]`. At the edges of the buffer the halo is truncated, so that
+/// `core_offset` is smaller than `halo` on the first sub-range and `padded`
+/// ends with the buffer on the last one.
+///
+/// The sequences must have the same length, otherwise
+/// [`ParError::LengthMismatch`] is returned before running any kernel.
+pub fn par_stencil<T: Send + Sync>(
+    src: &[T],
+    dest: &mut [T],
+    halo: usize,
+    num_threads: usize,
+    fr: Arc<StencilKernelFun<T>>,
+) -> Result<(), ParError> {
+    crate::check_len(src.len(), dest.len())?;
+    let len = src.len();
+    exec::run_chunks(num_threads, split::chunks(dest, num_threads), |(r, d)| {
+        let start = r.start.saturating_sub(halo);
+        let end = (r.end + halo).min(len);
+        fr(&src[start..end], r.start - start, d)
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_stencil_test() -> Result<(), ParError> {
+        // 3-point moving sum, clamped at the edges
+        let src: Vec<i32> = (0..100).collect();
+        let mut dest = vec![0_i32; src.len()];
+        let kernel_fun = |p: &[i32], off: usize, d: &mut [i32]| {
+            for (i, d) in d.iter_mut().enumerate() {
+                let c = off + i;
+                *d = p[c.saturating_sub(1)..(c + 2).min(p.len())].iter().sum();
+            }
+        };
+        par_stencil(&src, &mut dest, 1, 4, kernel!(kernel_fun))?;
+        let expected: Vec<i32> = (0..100)
+            .map(|i: i32| ((i - 1).max(0)..=(i + 1).min(99)).sum())
+            .collect();
+        assert_eq!(dest, expected);
+        let r = par_stencil(&src[1..], &mut dest, 1, 4, kernel!(kernel_fun));
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch { src: 99, dest: 100 })
+        ));
+        assert_eq!(dest, expected);
+        Ok(())
+    }
+}
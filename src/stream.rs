@@ -0,0 +1,153 @@
+//! Processing of blocks produced while the call runs.
+
+use crate::{exec, ParConfig, ParError};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+type BlockFun<'a, B, R> = dyn Fn(B) -> R + Send + Sync + 'a;
+
+// Results waiting for the ones of earlier blocks.
+struct Reorder<R> {
+    next: usize,
+    pending: BTreeMap<usize, R>,
+}
+
+fn lock<Q>(m: &Mutex<Q>) -> MutexGuard<'_, Q> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+//-----------------------------------------------------------------------------
+/// Apply `fr` to each block pulled from `input` and send the results to
+/// `output` in input order.
+///
+/// `input` can be an `mpsc::Receiver`, yielding the blocks sent until all its
+/// senders are dropped, or any iterator, e.g. over `Vec<T>` blocks read from
+/// a file. Each worker pulls the next block as soon as it is done with the
+/// previous one; results completed ahead of an earlier block are held back
+/// until that block completes. The call returns once `input` is exhausted or
+/// the receiver of `output` is dropped, and `output` is then dropped.
+///
+/// ```rust,ignore
+/// let (tx, rx) = mpsc::channel();
+/// std::thread::spawn(move || read_blocks(tx));
+/// let (out_tx, out_rx) = mpsc::channel();
+/// par_stream(rx, out_tx, 4, kernel!(|b: Vec<u8>| checksum(&b)))?;
+/// ```
+pub fn par_stream<I, B, R>(
+    input: I,
+    output: Sender<R>,
+    num_threads: usize,
+    fr: Arc<BlockFun<B, R>>,
+) -> Result<(), ParError>
+where
+    I: IntoIterator<Item = B>,
+    I::IntoIter: Send,
+    R: Send,
+{
+    let cfg = ParConfig::builder().threads(num_threads).build();
+    par_stream_with(&cfg, input, output, fr)
+}
+
+//-----------------------------------------------------------------------------
+/// Same as [`par_stream`] with the number of threads taken from `cfg`; with
+/// [`ParConfigBuilder::unordered`](crate::ParConfigBuilder::unordered), each
+/// result is sent as soon as it completes, in no particular order.
+pub fn par_stream_with<I, B, R>(
+    cfg: &ParConfig,
+    input: I,
+    output: Sender<R>,
+    fr: Arc<BlockFun<B, R>>,
+) -> Result<(), ParError>
+where
+    I: IntoIterator<Item = B>,
+    I::IntoIter: Send,
+    R: Send,
+{
+    let unordered = cfg.unordered();
+    let input = Mutex::new(input.into_iter().enumerate());
+    let reorder = Mutex::new(Reorder {
+        next: 0,
+        pending: BTreeMap::new(),
+    });
+    let hung_up = AtomicBool::new(false);
+    let workers = crate::resolve_threads(cfg.num_threads());
+    exec::run(workers, (0..workers).collect(), |_| {
+        while !hung_up.load(Ordering::Relaxed) {
+            // the input is unlocked before running the kernel
+            let Some((i, block)) = lock(&input).next() else {
+                return;
+            };
+            let r = fr(block);
+            if unordered {
+                if output.send(r).is_err() {
+                    hung_up.store(true, Ordering::Relaxed);
+                }
+                continue;
+            }
+            let mut reorder = lock(&reorder);
+            let Reorder { next, pending } = &mut *reorder;
+            pending.insert(i, r);
+            while let Some(r) = pending.remove(next) {
+                *next += 1;
+                if output.send(r).is_err() {
+                    hung_up.store(true, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    use std::sync::mpsc;
+    #[test]
+    fn par_stream_test() -> Result<(), ParError> {
+        let (tx, rx) = mpsc::channel();
+        let producer = std::thread::spawn(move || {
+            for i in 0..100_u64 {
+                tx.send(vec![i; (i % 7) as usize + 1]).unwrap();
+            }
+        });
+        let (out_tx, out_rx) = mpsc::channel();
+        let kernel_fun = |b: Vec<u64>| {
+            // later blocks complete first
+            std::thread::sleep(std::time::Duration::from_micros(100 - b[0]));
+            b.iter().sum::<u64>()
+        };
+        par_stream(rx, out_tx, 4, kernel!(kernel_fun))?;
+        producer.join().unwrap();
+        let sums: Vec<u64> = out_rx.iter().collect();
+        assert_eq!(sums, (0..100).map(|i| i * (i % 7 + 1)).collect::<Vec<_>>());
+        // iterator input, receiver dropped early
+        let (out_tx, out_rx) = mpsc::channel();
+        drop(out_rx);
+        let blocks = (0..1000).map(|i| vec![i; 10]);
+        par_stream(blocks, out_tx, 3, kernel!(|b: Vec<i32>| b.len()))?;
+        // completion order
+        let cfg = ParConfig::builder().threads(4).unordered(true).build();
+        let (out_tx, out_rx) = mpsc::channel();
+        let blocks = (0..100_u64).map(|i| vec![i; 3]);
+        par_stream_with(&cfg, blocks, out_tx, kernel!(kernel_fun))?;
+        let mut sums: Vec<u64> = out_rx.iter().collect();
+        sums.sort();
+        assert_eq!(sums, (0..100).map(|i| i * 3).collect::<Vec<_>>());
+        // reset from a shared builder
+        let builder = ParConfig::builder().threads(4).unordered(true);
+        let cfg = builder.unordered(false).build();
+        assert!(!cfg.unordered());
+        let (out_tx, out_rx) = mpsc::channel();
+        let blocks = (0..100_u64).map(|i| vec![i; 3]);
+        par_stream_with(&cfg, blocks, out_tx, kernel!(kernel_fun))?;
+        let sums: Vec<u64> = out_rx.iter().collect();
+        assert_eq!(sums, (0..100).map(|i| i * 3).collect::<Vec<_>>());
+        Ok(())
+    }
+}
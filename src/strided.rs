@@ -0,0 +1,111 @@
+//! Processing every k-th element of a sequence.
+
+use crate::{exec, split, ParError};
+use std::sync::Arc;
+
+type StridedKernelFun<'a, T> = dyn Fn(StridedSliceMut<'_, T>) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Mutable view of the elements `offset, offset + stride, offset + 2 * stride, ..`
+/// of a slice, e.g. one channel of interleaved data.
+pub struct StridedSliceMut<'a, T> {
+    data: &'a mut [T],
+    stride: usize,
+    offset: usize,
+}
+
+impl<'a, T> StridedSliceMut<'a, T> {
+    /// View of every `stride`-th element of `data` starting at `offset`.
+    pub fn new(data: &'a mut [T], stride: usize, offset: usize) -> Self {
+        assert!(
+            stride > 0 && offset < stride,
+            "offset must be smaller than a non-zero stride"
+        );
+        Self {
+            data,
+            stride,
+            offset,
+        }
+    }
+    /// Number of elements in the view.
+    pub fn len(&self) -> usize {
+        (self.data.len() + self.stride - self.offset - 1) / self.stride
+    }
+    /// Return `true` if the view has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Element `i` of the view.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.data.get(self.offset + i * self.stride)
+    }
+    /// Mutable element `i` of the view.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.data.get_mut(self.offset + i * self.stride)
+    }
+    /// Iterate over the elements of the view.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter().skip(self.offset).step_by(self.stride)
+    }
+    /// Iterate mutably over the elements of the view.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.data.iter_mut().skip(self.offset).step_by(self.stride)
+    }
+}
+
+//-----------------------------------------------------------------------------
+/// Modify in-place the elements `offset, offset + stride, ..` of `dest`.
+///
+/// `dest` is split at multiples of `stride` so that each thread receives a
+/// view over whole groups of `stride` elements, e.g. whole RGBA pixels.
+pub fn par_in_place_strided<T: Send>(
+    dest: &mut [T],
+    stride: usize,
+    offset: usize,
+    num_threads: usize,
+    fr: Arc<StridedKernelFun<T>>,
+) -> Result<(), ParError> {
+    assert!(
+        stride > 0 && offset < stride,
+        "offset must be smaller than a non-zero stride"
+    );
+    let len = dest.len();
+    let frames = split::ranges(len.div_ceil(stride), num_threads);
+    let ranges: Vec<_> = frames
+        .into_iter()
+        .map(|f| f.start * stride..(f.end * stride).min(len))
+        .collect();
+    let parts = split::split_ranges(dest, &ranges);
+    exec::run(num_threads, parts, |d| {
+        fr(StridedSliceMut::new(d, stride, offset))
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_in_place_strided_test() -> Result<(), ParError> {
+        // set alpha channel of RGBA pixels
+        let mut rgba = vec![7_u8; 4 * 101];
+        let kernel_fun = |mut alpha: StridedSliceMut<'_, u8>| {
+            for a in alpha.iter_mut() {
+                *a = 255;
+            }
+        };
+        par_in_place_strided(&mut rgba, 4, 3, 4, kernel!(kernel_fun))?;
+        for (i, e) in rgba.iter().enumerate() {
+            assert_eq!(*e, if i % 4 == 3 { 255 } else { 7 });
+        }
+        let mut data = [0, 1, 2, 3, 4];
+        let mut v = StridedSliceMut::new(&mut data, 2, 1);
+        assert_eq!(v.len(), 2);
+        *v.get_mut(1).unwrap() = 9;
+        assert_eq!(v.iter().copied().collect::<Vec<_>>(), vec![1, 9]);
+        Ok(())
+    }
+}
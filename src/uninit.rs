@@ -0,0 +1,89 @@
+//! Mapping into uninitialized destinations.
+
+use crate::{exec, split, ElemFun, ParError};
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+//-----------------------------------------------------------------------------
+/// Map each element of `src` into the matching uninitialized element of
+/// `dest`, returning the initialized elements.
+///
+/// The kernel returns the value of each element, so that every element of
+/// `dest` is written exactly once before being assumed initialized. The
+/// sequences must have the same length, otherwise
+/// [`ParError::LengthMismatch`] is returned before running any kernel. If a
+/// kernel panics the elements already written are leaked, not dropped.
+pub fn par_map_uninit<'d, S: Sync, D: Send>(
+    src: &[S],
+    dest: &'d mut [MaybeUninit<D>],
+    num_threads: usize,
+    fr: Arc<ElemFun<S, D>>,
+) -> Result<&'d mut [D], ParError> {
+    crate::check_len(src.len(), dest.len())?;
+    let len = dest.len();
+    exec::run_chunks(
+        num_threads,
+        split::chunks((src, &mut *dest), num_threads),
+        |(_, (s, d))| {
+            for (d, s) in d.iter_mut().zip(s) {
+                d.write(fr(s));
+            }
+        },
+    )?;
+    // SAFETY: the `len` elements were written by the sub-range owning them,
+    // all the threads completed successfully; `MaybeUninit<D>` has the same
+    // layout as `D`.
+    Ok(unsafe { std::slice::from_raw_parts_mut(dest.as_mut_ptr().cast::<D>(), len) })
+}
+
+//-----------------------------------------------------------------------------
+/// Map each element of `src` into a newly allocated vector.
+///
+/// The vector is allocated with the length of `src` and filled through
+/// [`par_map_uninit`] using the same sub-ranges as [`par_map_to`](crate::par_map_to):
+/// the destination is never zero-filled and no element is copied after being
+/// computed.
+pub fn par_map_collect<S: Sync, D: Send>(
+    src: &[S],
+    num_threads: usize,
+    fr: Arc<ElemFun<S, D>>,
+) -> Result<Vec<D>, ParError> {
+    let len = src.len();
+    let mut dest = Vec::with_capacity(len);
+    par_map_uninit(src, &mut dest.spare_capacity_mut()[..len], num_threads, fr)?;
+    // SAFETY: `par_map_uninit` initialized the first `len` elements.
+    unsafe { dest.set_len(len) };
+    Ok(dest)
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_map_uninit_test() -> Result<(), ParError> {
+        let src: Vec<u32> = (0..1000).collect();
+        let mut dest: Vec<MaybeUninit<String>> = (0..1200).map(|_| MaybeUninit::uninit()).collect();
+        let r = par_map_uninit(&src, &mut dest, 3, kernel!(|e: &u32| e.to_string()));
+        assert!(matches!(
+            r,
+            Err(ParError::LengthMismatch {
+                src: 1000,
+                dest: 1200
+            })
+        ));
+        let out = par_map_uninit(&src, &mut dest[..1000], 3, kernel!(|e: &u32| e.to_string()))?;
+        assert_eq!(out.len(), 1000);
+        assert_eq!(out[999], "999");
+        // the strings are owned by `dest` which does not drop them
+        for s in out.iter_mut() {
+            drop(std::mem::take(s));
+        }
+        let doubled = par_map_collect(&src, 4, kernel!(|e: &u32| u64::from(*e) * 2))?;
+        assert_eq!(doubled, (0..2000).step_by(2).collect::<Vec<u64>>());
+        assert!(par_map_collect(&src[..0], 4, kernel!(|e: &u32| *e))?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Overlapping windows.
+
+use crate::{exec, split, ParError};
+use std::ops::Range;
+use std::sync::Arc;
+
+type WindowKernelFun<'a, T> = dyn Fn(usize, &mut [T]) + Send + Sync + 'a;
+type BlendFun<'a, T> = dyn Fn(usize, &mut [T], &[T]) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Resolution of the elements written by more than one window in
+/// [`par_in_place_windows`].
+pub enum WindowMerge<'a, T> {
+    /// The window starting last wins.
+    Last,
+    /// `Fn(offset, acc, next)` combines into `acc` the output `next` of a later
+    /// window over the same elements, `offset` being the position of `acc[0]`
+    /// in the sequence; called in window order, e.g. `acc += next` for
+    /// overlap-add.
+    Blend(Arc<BlendFun<'a, T>>),
+}
+
+//-----------------------------------------------------------------------------
+/// Run the kernel on windows of `window` elements starting every
+/// `window - overlap` elements, consecutive windows sharing `overlap`
+/// elements; the last window is truncated at the end of `dest`.
+///
+/// The kernel receives `Fn(offset, window)`: a copy of the window and the
+/// position of its first element in `dest`. The windows are written back once
+/// all of them have been processed, the elements shared by several windows
+/// being resolved according to `merge`.
+///
+/// ```rust,ignore
+/// // overlap-add filtering with 50% overlap
+/// let merge = WindowMerge::Blend(kernel!(|_, acc: &mut [f32], next: &[f32]| {
+///     acc.iter_mut().zip(next).for_each(|(a, n)| *a += n)
+/// }));
+/// par_in_place_windows(&mut samples, 1024, 512, 8, kernel!(filter), merge)?;
+/// ```
+///
+/// Panics if `window` is `0` or `overlap >= window`.
+pub fn par_in_place_windows<T: Clone + Send + Sync>(
+    dest: &mut [T],
+    window: usize,
+    overlap: usize,
+    num_threads: usize,
+    fr: Arc<WindowKernelFun<T>>,
+    merge: WindowMerge<T>,
+) -> Result<(), ParError> {
+    assert!(overlap < window, "overlap must be smaller than the window");
+    let len = dest.len();
+    if len == 0 {
+        return Ok(());
+    }
+    let hop = window - overlap;
+    let count = 1 + len.saturating_sub(window).div_ceil(hop);
+    let windows: Vec<Range<usize>> = (0..count)
+        .map(|k| k * hop..(k * hop + window).min(len))
+        .collect();
+    let src = &*dest;
+    let outputs = exec::run(num_threads, windows.clone(), |w| {
+        let mut out = src[w.clone()].to_vec();
+        fr(w.start, &mut out);
+        out
+    })?;
+    // region `k` starts with window `k` and ends with the next one
+    let regions: Vec<Range<usize>> = windows
+        .iter()
+        .enumerate()
+        .map(|(k, w)| w.start..windows.get(k + 1).map_or(len, |n| n.start))
+        .collect();
+    let parts: Vec<_> = split::split_ranges(dest, &regions)
+        .into_iter()
+        .zip(regions.iter().map(|r| r.start))
+        .enumerate()
+        .collect();
+    exec::run(num_threads, parts, |(k, (d, start))| {
+        // the windows covering the region cover growing prefixes of it
+        let first = (0..=k).find(|j| windows[*j].end > start).unwrap_or(k);
+        let mut written = 0;
+        for (w, out) in windows[first..=k].iter().zip(&outputs[first..=k]) {
+            let covered = (w.end - start).min(d.len());
+            let out = &out[start - w.start..][..covered];
+            let shared = written.min(covered);
+            match &merge {
+                WindowMerge::Last => d[..shared].clone_from_slice(&out[..shared]),
+                WindowMerge::Blend(f) if shared > 0 => f(start, &mut d[..shared], &out[..shared]),
+                WindowMerge::Blend(_) => {}
+            }
+            d[shared..covered].clone_from_slice(&out[shared..]);
+            written = written.max(covered);
+        }
+    })?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_in_place_windows_test() -> Result<(), ParError> {
+        // each window is filled with its offset, the last one wins
+        let mut dest = vec![0_usize; 10];
+        let fill = kernel!(|off: usize, w: &mut [usize]| w.fill(off));
+        par_in_place_windows(&mut dest, 4, 1, 3, fill.clone(), WindowMerge::Last)?;
+        assert_eq!(dest, [0, 0, 0, 3, 3, 3, 6, 6, 6, 6]);
+        // overlap-add of windows of ones counts the windows covering each
+        // element
+        let mut dest = vec![1_u32; 20];
+        let add = |_: usize, acc: &mut [u32], next: &[u32]| {
+            acc.iter_mut().zip(next).for_each(|(a, n)| *a += n)
+        };
+        let ident = kernel!(|_: usize, _: &mut [u32]| {});
+        for (window, overlap) in [(8, 6), (4, 2), (5, 0)] {
+            let mut d = dest.clone();
+            par_in_place_windows(
+                &mut d,
+                window,
+                overlap,
+                4,
+                ident.clone(),
+                WindowMerge::Blend(kernel!(add)),
+            )?;
+            let hop = window - overlap;
+            let expected: Vec<u32> = (0..20_usize)
+                .map(|i| {
+                    let starts = (0..20)
+                        .step_by(hop)
+                        .take_while(|s| *s == 0 || s + overlap < 20);
+                    starts.filter(|s| (*s..s + window).contains(&i)).count() as u32
+                })
+                .collect();
+            assert_eq!(d, expected, "window {window} overlap {overlap}");
+        }
+        // a single truncated window
+        par_in_place_windows(&mut dest[..3], 8, 2, 4, ident, WindowMerge::Last)?;
+        assert!(dest.iter().all(|e| *e == 1));
+        Ok(())
+    }
+}
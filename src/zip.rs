@@ -0,0 +1,103 @@
+//! Kernels reading from multiple source sequences.
+
+use crate::{exec, split, ParError};
+use std::sync::Arc;
+
+type ZipKernelFun<'a, A, B, C> = dyn Fn(&[A], &[B], &mut [C]) + Send + Sync + 'a;
+
+//-----------------------------------------------------------------------------
+/// Map elements of two source sequences into elements of the destination
+/// sequence; the three sequences are split at the same offsets.
+pub fn par_zip_map<A: Sync, B: Sync, C: Send>(
+    a: &[A],
+    b: &[B],
+    dest: &mut [C],
+    num_threads: usize,
+    fr: Arc<ZipKernelFun<A, B, C>>,
+) -> Result<(), ParError> {
+    crate::check_len(a.len(), dest.len())?;
+    crate::check_len(b.len(), dest.len())?;
+    let chunks = split::chunks(((a, b), dest), num_threads);
+    exec::run_chunks(num_threads, chunks, |(_, ((a, b), d))| fr(a, b, d))?;
+    Ok(())
+}
+
+//-----------------------------------------------------------------------------
+// `par_map<N>` functions reading from `N` sources with kernel signature
+// `Fn(&[S0], .., &[SN-1], &mut [D])`.
+macro_rules! par_map_n {
+    ($(#[$doc:meta])* $name:ident, $alias:ident, $($src:ident: $S:ident),+) => {
+        type $alias<'a, $($S,)+ D> = dyn Fn($(&[$S],)+ &mut [D]) + Send + Sync + 'a;
+        $(#[$doc])*
+        #[allow(clippy::too_many_arguments)]
+        pub fn $name<$($S: Sync,)+ D: Send>(
+            $($src: &[$S],)+
+            dest: &mut [D],
+            num_threads: usize,
+            fr: Arc<$alias<$($S,)+ D>>,
+        ) -> Result<(), ParError> {
+            $(crate::check_len($src.len(), dest.len())?;)+
+            let chunks = split::chunks((($($src,)+), dest), num_threads);
+            exec::run_chunks(num_threads, chunks, |(_, (($($src,)+), dest))| fr($($src,)+ dest))?;
+            Ok(())
+        }
+    };
+}
+
+par_map_n!(
+    /// Same as [`par_zip_map`] with three source sequences.
+    par_map3, Map3KernelFun, a: A, b: B, c: C
+);
+par_map_n!(
+    /// Same as [`par_zip_map`] with four source sequences.
+    par_map4, Map4KernelFun, a: A, b: B, c: C, d: E
+);
+par_map_n!(
+    /// Same as [`par_zip_map`] with five source sequences.
+    par_map5, Map5KernelFun, a: A, b: B, c: C, d: E, e: F
+);
+
+//-----------------------------------------------------------------------------
+//-----------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel;
+    #[test]
+    fn par_zip_map_test() -> Result<(), ParError> {
+        let a: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let b = vec![2_f32; a.len()];
+        let mut c = vec![0_f64; a.len()];
+        let kernel_fun = |a: &[f32], b: &[f32], c: &mut [f64]| {
+            for ((c, a), b) in c.iter_mut().zip(a).zip(b) {
+                *c = f64::from(a * b);
+            }
+        };
+        par_zip_map(&a, &b, &mut c, 3, kernel!(kernel_fun))?;
+        assert!(c.iter().enumerate().all(|(i, c)| *c == 2.0 * i as f64));
+        Ok(())
+    }
+    #[test]
+    fn par_map_n_test() -> Result<(), ParError> {
+        // blend three layers
+        let a = vec![10_u8; 64];
+        let b = vec![20_u8; 64];
+        let c = vec![30_u8; 64];
+        let mut dest = vec![0_u8; 64];
+        let kernel_fun = |a: &[u8], b: &[u8], c: &[u8], d: &mut [u8]| {
+            for (i, d) in d.iter_mut().enumerate() {
+                *d = ((u16::from(a[i]) + u16::from(b[i]) + u16::from(c[i])) / 3) as u8;
+            }
+        };
+        par_map3(&a, &b, &c, &mut dest, 3, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 20));
+        let kernel_fun = |a: &[u8], b: &[u8], c: &[u8], e: &[u8], d: &mut [u8]| {
+            for (i, d) in d.iter_mut().enumerate() {
+                *d = a[i] + b[i] + c[i] + e[i];
+            }
+        };
+        par_map4(&a, &b, &c, &a, &mut dest, 4, kernel!(kernel_fun))?;
+        assert!(dest.iter().all(|e| *e == 70));
+        Ok(())
+    }
+}